@@ -0,0 +1,42 @@
+#![no_main]
+
+// The main crate is a binary, so the parser module is pulled in by path.
+// Run with `cargo +nightly fuzz run parser` from the repository root.
+#[allow(dead_code)]
+#[path = "../../src/parser.rs"]
+mod parser;
+
+use libfuzzer_sys::fuzz_target;
+use parser::*;
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+
+    let parsers = [
+        make_number(),
+        make_word(),
+        make_capture(make_list(make_capture(make_number()), make_lit(", "))),
+        make_list(
+            make_seq(vec![make_capture(make_number()), make_lit(","), make_capture(make_number())]),
+            make_lit(" -> ")
+        ),
+        make_many(make_seq(vec![make_any(make_ws()), make_capture(make_word())])),
+    ];
+    for parser in parsers.iter() {
+        if let Ok((rest, _)) = parser.parse(text, text.chars()) {
+            assert!(text.ends_with(rest.as_str()));
+        }
+    }
+
+    // The first line is the pattern, the rest the text to match
+    if let Some((pattern, wildcard_text)) = text.split_once('\n') {
+        let _ = parse_wildcard(pattern, '*', wildcard_text);
+    }
+
+    for record in parse_records(text.as_bytes(), parsers[4].clone()) {
+        let _ = record;
+    }
+});
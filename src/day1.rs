@@ -1,12 +1,6 @@
 pub mod aoc {
 
-use std::io;
-
-#[derive(Debug)]
-struct Counter {
-    maxnew: [i32; 3],
-    current: i32,
-}
+use crate::util::input_sections;
 
 fn insert_calories(old_array: &[i32; 3], val: i32) -> [i32; 3] {
     let mut new_array = [0, 0, 0];
@@ -22,45 +16,19 @@ fn insert_calories(old_array: &[i32; 3], val: i32) -> [i32; 3] {
     new_array
 }
 
-fn process_line(line: &str, counter: Counter) -> Counter {
-    if line.is_empty() {
-
-        Counter{
-            maxnew: insert_calories(&counter.maxnew, counter.current),
-            current: 0
-        }
-    } else {
-        Counter{
-            maxnew: counter.maxnew,
-            current: counter.current + line.parse::<i32>().unwrap()
-        }
-    }
-}
-
 #[allow(dead_code)]
 pub fn day_main() {
-    let mut counter = Counter{
-        maxnew: [0, 0, 0],
-        current: 0,
-    };
-    loop {
-        let mut buffer = String::new();
-        match io::stdin().read_line(&mut buffer) {
-            Ok(0) => {
-                if counter.current != 0 {
-                    counter = process_line("", counter)
-                }
-                println!("Counter: {:?}", &counter);
-                println!("Sum of maxes: {}", counter.maxnew.iter().fold(0, |acc, x| x + acc));
-                break;
-            },
-            Ok(_) => {
-                let line = buffer.trim();
-                counter = process_line(line, counter);
-            },
-            Err(error) => panic!("{}", error)
-        }
+    let mut maxnew = [0, 0, 0];
+    for section in input_sections() {
+        let calories = section.lines
+            .iter()
+            .map(|(line_no, line)| line.trim().parse::<i32>()
+                .unwrap_or_else(|error| panic!("line {}: {}", line_no + 1, error)))
+            .sum();
+        maxnew = insert_calories(&maxnew, calories);
     }
+    println!("Top calories: {:?}", &maxnew);
+    println!("Sum of maxes: {}", maxnew.iter().fold(0, |acc, x| x + acc));
 }
 
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 pub mod aoc {
-    use crate::util::input_lines;
+    use crate::grid::{Grid, Point};
+    use crate::record::Recorder;
+    use crate::util::{expect_line, parse_lines};
 
     use std::collections::VecDeque;
+    use std::str::FromStr;
 
     #[derive(Clone, Copy)]
     struct Instruction {
@@ -9,34 +12,31 @@ pub mod aoc {
         latency: u32,
     }
 
-    fn parse_instruction(line: String) -> Instruction {
-        if line.is_empty() {
-            return Instruction{
-                diff: 0,
-                latency: 0,
-            };
-        }
-        if line == "noop" {
-            return Instruction{
-                diff: 0,
-                latency: 1,
-            };
-        }
-        if let Some(addx_arg) = line.strip_prefix("addx ") {
-            return Instruction{
-                diff: addx_arg.parse::<i32>().unwrap(),
-                latency: 2,
-            };
+    impl FromStr for Instruction {
+        type Err = String;
+
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            if line == "noop" {
+                return Ok(Instruction{
+                    diff: 0,
+                    latency: 1,
+                });
+            }
+            if let Some(addx_arg) = line.strip_prefix("addx ") {
+                return Ok(Instruction{
+                    diff: addx_arg.parse::<i32>().map_err(|error| error.to_string())?,
+                    latency: 2,
+                });
+            }
+            Err(format!("invalid instruction: '{}'", line))
         }
-        panic!("invalid instruction: '{}'", line)
     }
 
     #[allow(dead_code)]
     pub fn day_main_part() {
 
-        let mut program: VecDeque<Instruction> = input_lines()
-            .map(parse_instruction)
-            .filter(|instr| instr.latency > 0)
+        let mut program: VecDeque<Instruction> = parse_lines::<Instruction>()
+            .map(expect_line)
             .collect();
 
         let mut current: Option<Instruction> = program.pop_front();
@@ -44,6 +44,10 @@ pub mod aoc {
         let mut cycle: i32 = 1;
         let mut signal_strength = 0;
 
+        // The screen as drawn so far, recorded after every cycle
+        let mut recorder = Recorder::from_args(' ');
+        let mut screen = Grid::new(40, 6, ' ');
+
         while let Some(instr) = current {
             // print
             let beam_x = (cycle - 1) % 40;
@@ -53,6 +57,11 @@ pub mod aoc {
             if beam_x == 39 {
                 println!("");
             }
+            if let Some(recorder) = recorder.as_mut() {
+                let beam_y = ((cycle - 1) / 40) % 6;
+                screen.set(&Point{ x: beam_x, y: beam_y }, if sprite_visible { '#' } else { '.' });
+                recorder.capture(&screen);
+            }
 
             let next_x = if instr.latency == 1 && instr.diff != 0 {
                 // println!("{}: scheduling +{} for after this cycle. X = {}", cycle, instr.diff, x);
@@ -79,6 +88,11 @@ pub mod aoc {
             }
         }
         println!("signal strength: {}", signal_strength);
+
+        if let Some(mut recorder) = recorder {
+            recorder.capture_last_at(&screen, Point{ x: 0, y: 0 });
+            recorder.finish();
+        }
     }
 
     #[allow(non_upper_case_globals, dead_code)]
@@ -1,7 +1,7 @@
 #[allow(dead_code)]
 pub mod aoc {
     use crate::parser;
-    use crate::util::input_all;
+    use crate::util::input_records;
     use std::rc::Rc;
     use std::collections::VecDeque;
 
@@ -49,50 +49,53 @@ pub mod aoc {
       If false: throw to monkey 0";
 
     fn make_monkey_parser() -> Rc<dyn parser::Parser> {
+        parser::make_many(make_monkey_record_parser())
+    }
+
+    fn make_monkey_record_parser() -> Rc<dyn parser::Parser> {
         use parser::*;
 
         let ws = make_many(make_ws());
-        let number = make_many(make_range('0', '9'));
+        let number = make_named("number", make_many(make_range('0', '9')));
 
-        let monkey_idx = make_seq(vec![
+        let monkey_idx = make_named("monkey_idx", make_seq(vec![
             make_lit("Monkey "), make_capture(number.clone()), make_char(':')
-        ]);
-        let monkey_items = make_seq(vec![
+        ]));
+        let monkey_items = make_named("monkey_items", make_seq(vec![
             make_lit("Starting items: "), make_list(make_capture(number.clone()), make_lit(", "))
-        ]);
+        ]));
 
         let operator = make_alt(make_char('+'), make_char('*'));
-        let monkey_op = make_seq(vec![
+        let monkey_op = make_named("monkey_op", make_seq(vec![
             make_lit("Operation: new = "),
             make_capture(make_word()),
             ws.clone(),
             make_capture(operator),
             ws.clone(),
             make_capture(make_word())
-        ]);
+        ]));
 
-        let monkey_test = make_seq(vec![
+        let monkey_test = make_named("monkey_test", make_seq(vec![
             make_lit("Test: divisible by "), make_capture(number.clone())
-        ]);
+        ]));
 
-        let monkey_true = make_seq(vec![
+        let monkey_true = make_named("monkey_true", make_seq(vec![
             make_lit("If true: throw to monkey "), make_capture(number.clone())
-        ]);
+        ]));
 
-        let monkey_false = make_seq(vec![
+        let monkey_false = make_named("monkey_false", make_seq(vec![
             make_lit("If false: throw to monkey "), make_capture(number.clone())
-        ]);
+        ]));
 
-        let monkey_all = make_seq(vec![
+        make_named("monkey", make_seq(vec![
+            make_any(make_ws()),
             monkey_idx, ws.clone(),
             monkey_items, ws.clone(),
             monkey_op, ws.clone(),
             monkey_test, ws.clone(),
             monkey_true, ws.clone(),
             monkey_false, make_any(make_ws()),
-        ]);
-
-        make_many(monkey_all)
+        ]))
     }
 
     fn construct_monkey(capture: crate::parser::Captured) -> Monkey {
@@ -169,6 +172,12 @@ pub mod aoc {
         }
     }
 
+    fn read_monkeys() -> Vec<Monkey> {
+        input_records(make_monkey_record_parser())
+            .map(|record| construct_monkey(record.unwrap()))
+            .collect()
+    }
+
     fn apply_operation(worry_level: i64, op: &Operation) -> i64 {
         let lhs = match op.op1 {
             Operand::Old => worry_level,
@@ -234,8 +243,7 @@ pub mod aoc {
 
     #[allow(dead_code)]
     fn day_main_part() {
-        let monkey_text = &input_all();
-        let monkeys = parse_input(&monkey_text);
+        let monkeys = read_monkeys();
         let modulo = find_modulo(&monkeys);
 
         // {
@@ -1,193 +1,143 @@
 #[allow(dead_code)]
 pub mod aoc {
-    use std::collections::BinaryHeap;
-    use std::cmp::Reverse;
-
-    use crate::util::input_lines;
+    use crate::grid::{self, Connectivity, Direction4, Grid, Markers, RenderOptions, YAxis};
+    use crate::image::{heat, Rgb};
+    use crate::search::{a_star, distance_map};
+    use crate::util::{has_flag, input_lines_indices, option_value};
+
+    type Point = grid::Point<i32>;
+
+    fn read_grid() -> (Grid<i8>, Point, Point) {
+        let mut markers = Markers::default();
+        let grid = Grid::from_lines(input_lines_indices(), |val, pos| {
+            let height = match val {
+                'S' => 'a',
+                'E' => 'z',
+                val if val.is_ascii_lowercase() => val,
+                val => return Err(format!("Expected either S, E, or a-z, but got '{}'", val)),
+            };
+            if val == 'S' || val == 'E' {
+                markers.mark(val, pos);
+            }
+            Ok(height as i8 - 'a' as i8)
+        }).unwrap_or_else(|error| panic!("{}", error));
 
-    struct Grid<T: Copy> {
-        data: Vec<T>,
-        width: i32,
-        height: i32,
+        let marker = |ch| {
+            let p = markers.one(ch).unwrap();
+            Point{ x: p.x as i32, y: p.y as i32 }
+        };
+        (grid, marker('S'), marker('E'))
     }
 
-    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-    struct Point {
-        x: i32,
-        y: i32,
+    // Neighbors reachable by climbing at most one step up
+    fn moves(grid: &Grid<i8>, from: &Point) -> Vec<Point> {
+        grid.neighbors4(from)
+            .filter(|to| grid.get(to) - grid.get(from) <= 1)
+            .collect()
     }
 
-    impl <T: Copy> Grid<T> {
-
-        fn new(width: i32, height: i32, value: T) -> Grid<T> {
-            Grid{
-                data: vec![value; (width * height) as usize],
-                width: width,
-                height: height,
-            }
-        }
-
-        fn get(&self, p: &Point) -> T {
-            assert!(p.x >= 0 && p.x < self.width);
-            assert!(p.y >= 0 && p.y < self.height);
-            self.data[(p.x + p.y * self.width) as usize]
-        }
-
-        fn set(&mut self, p: &Point, value: T) {
-            assert!(p.x >= 0 && p.x < self.width);
-            assert!(p.y >= 0 && p.y < self.height);
-            self.data[(p.x + p.y * self.width) as usize] = value
-        }
+    #[derive(Clone, Copy)]
+    struct RouteCell {
+        height: i8,
+        step: Option<Direction4>,
+        is_end: bool,
     }
 
-    impl std::fmt::Debug for Grid<i8> {
+    // Drawn like in the puzzle statement: arrows along the route, 'E' at its
+    // end and '.' everywhere else
+    impl std::fmt::Display for RouteCell {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            for row in 0..self.height {
-                for col in 0..self.width {
-                    let height = self.get(&Point{ x: col, y: row });
-                    let char = (height + 'a' as i8) as u8 as char;
-                    write!(f, "{}", char)?
-                }
-                writeln!(f, "")?
+            match (self.step, self.is_end) {
+                (Some(direction), _) => write!(f, "{}", direction),
+                (None, true) => write!(f, "E"),
+                (None, false) => write!(f, "."),
             }
-            Ok(())
         }
     }
 
-    fn read_grid(input: &Vec<String>) -> (Grid<i8>, Point, Point) {
-        assert!(input.len() > 0);
-        let cols = input[0].len();
-        let rows = input.len();
-        let mut grid = Grid::new(cols as i32, rows as i32, 0);
-        let mut start: Option<Point> = None;
-        let mut end: Option<Point> = None;
-        for row in 0..rows {
-            let line = &input[row];
-            assert!(line.len() == cols);
-
-            for (col, val) in line.char_indices() {
-                let pos = Point{ x: col as i32, y: row as i32 };
-                let height= match val {
-                    'S' => {
-                        start = Some(pos.clone());
-                        'a'
-                    },
-                    'E' => {
-                        end = Some(pos.clone());
-                        'z'
-                    },
-                    val if val >= 'a' && val <= 'z' => val,
-                    val => panic!("Expected either S, E, or a-z, but got '{}'", val)
-                } as i8 - 'a' as i8;
-                grid.set(&pos, height);
-            }
-        }
-        (grid, start.unwrap(), end.unwrap())
+    fn height_color(height: i8) -> Rgb {
+        heat(height as f64, 0.0, 25.0)
     }
 
-    fn a_star<HeurT, ViableT, DoneT>(grid: &Grid<i8>, start: &Point,
-        h: &HeurT, viable: &ViableT, done: &DoneT) -> u32
-    where
-        HeurT: Fn(&Point) -> f32,
-        ViableT: Fn(&Point, &Point) -> bool,
-        DoneT: Fn(&Point) -> bool,
-    {
-        #[derive(PartialEq, PartialOrd, Eq, Ord)]
-        struct Candidate {
-            cost: i32,
-            point: Point,
+    fn route_map(grid: &Grid<i8>, route: &[Point]) -> Grid<RouteCell> {
+        let mut map = Grid::new(grid.width(), grid.height(), RouteCell{ height: 0, step: None, is_end: false });
+        for p in grid.points() {
+            map.set(&p, RouteCell{ height: grid.get(&p), step: None, is_end: false });
         }
-
-        let make_candidate = |current_dist: i32, from: &Point| {
-            Reverse(Candidate{
-                cost: current_dist * 100 + (h(from) * 100.0) as i32,
-                point: from.clone()
-            })
-        };
-
-        let mut frontier: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
-        let mut distances = Grid::new(grid.width, grid.height, std::u32::MAX);
-
-        distances.set(start, 0);
-        frontier.push(make_candidate(0, start));
-        while let Some(Reverse(Candidate{ cost: _, point })) = frontier.pop() {
-            let current_dist = distances.get(&point);
-            let new_dist = current_dist + 1;
-            for (xd, yd) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-                let new_point = Point{ x: point.x + xd, y: point.y + yd };
-                if !viable(&point, &new_point) {
-                    continue
-                }
-                if done(&new_point) {
-                    return new_dist
-                }
-                if distances.get(&new_point) <= new_dist { continue };
-                distances.set(&new_point, new_dist);
-                frontier.push(make_candidate(new_dist as i32, &new_point));
-            }
+        for step in route.windows(2) {
+            let mut cell = map.get(&step[0]);
+            cell.step = Direction4::from_vector(&(step[1] - step[0]), YAxis::Down);
+            map.set(&step[0], cell);
         }
-        panic!("Did not find any path!");
+        if let Some(end) = route.last() {
+            let mut cell = map.get(end);
+            cell.is_end = true;
+            map.set(end, cell);
+        }
+        map
     }
 
-    pub fn day_main() {
-        let lines: Vec<String> = input_lines().collect();
-        let (grid, start, end) = read_grid(&lines);
+    // Pixels per cell in each direction of the route image
+    const GLYPH_SIZE: i64 = 5;
+    const ARROW_UP: &str = "..#..\n.###.\n#.#.#\n..#..\n..#..";
 
-        // println!("{:?}", grid);
-        println!("start: {:?}, end: {:?}", start, end);
+    // The height map with each route cell drawn as a white arrow, and the end
+    // as a white square
+    fn route_image(map: &Grid<RouteCell>) -> Grid<Rgb> {
+        let mut arrows: Vec<Grid<bool>> = vec![Grid::from_str_with(ARROW_UP, |ch, _| Ok(ch == '#')).unwrap()];
+        while arrows.len() < Direction4::ALL.len() {
+            arrows.push(arrows.last().unwrap().rotate90());
+        }
 
-        {
-            let heuristic = |from: &Point| {
-                let xd = (from.x - end.x).abs() as f32;
-                let yd = (from.y - end.y).abs() as f32;
-                (xd * xd + yd * yd).sqrt()
+        let mut image = Grid::new(map.width() * GLYPH_SIZE, map.height() * GLYPH_SIZE, Rgb(0, 0, 0));
+        for p in image.points() {
+            let cell = map.get(&grid::Point{ x: p.x / GLYPH_SIZE, y: p.y / GLYPH_SIZE });
+            let glyph_point = grid::Point{ x: p.x % GLYPH_SIZE, y: p.y % GLYPH_SIZE };
+            let lit = match cell.step {
+                Some(direction) => arrows[direction as usize].get(&glyph_point),
+                None => cell.is_end,
             };
+            image.set(&p, if lit { Rgb(255, 255, 255) } else { height_color(cell.height) });
+        }
+        image
+    }
 
-            let viable = |from: &Point, to: &Point| {
-                if to.x < 0 || to.x >= grid.width || to.y < 0 || to.y >= grid.height {
-                    return false
-                }
-                let old_height = grid.get(from);
-                let new_height = grid.get(to);
-                if new_height - old_height > 1 {
-                    return false
-                }
-                true
-            };
+    pub fn day_main() {
+        let (grid, start, end) = read_grid();
 
-            let done = |p: &Point| {
-                p == &end
-            };
+        println!("start: {:?}, end: {:?}", start, end);
 
-            let distance = a_star(&grid, &start, &heuristic, &viable, &done);
-            println!("minimum distance forward: {}", distance);
+        // Climbing up from the start, the route is drawn on the height map
+        let route = {
+            let manhattan = |from: &Point| (end - *from).l1_norm() as u32;
+            let steps = |from: &Point| moves(&grid, from).into_iter().map(|to| (to, 1));
+            a_star(start, steps, manhattan, |p| *p == end)
+        };
+        match &route {
+            Some(route) => println!("minimum distance forward: {}", route.cost),
+            None => println!("no path forward"),
         }
 
         {
-            let heuristic = |from: &Point| {
-                let xd = (from.x - end.x).abs() as f32;
-                let yd = (from.y - end.y).abs() as f32;
-                (xd * xd + yd * yd).sqrt()
-            };
-
-            let viable = |from: &Point, to: &Point| {
-                if to.x < 0 || to.x >= grid.width || to.y < 0 || to.y >= grid.height {
-                    return false
-                }
-                let old_height = grid.get(from);
-                let new_height = grid.get(to);
-                if new_height - old_height < -1 {
-                    return false
-                }
-                true
-            };
-
-            let done = |p: &Point| {
-                grid.get(p) == 0
-            };
+            // Walking down from the end reaches every lowest cell at once
+            let end = grid::Point{ x: end.x as i64, y: end.y as i64 };
+            let downhill = |from: &grid::Point, to: &grid::Point| grid.get(from) - grid.get(to) <= 1;
+            let distances = distance_map(&grid, [end], Connectivity::Four, downhill);
+            let lowest = grid.points().filter(|p| grid.get(p) == 0);
+            match lowest.filter_map(|p| distances.get(&p)).min() {
+                Some(distance) => println!("minimum distance backward: {}", distance),
+                None => println!("no path backward"),
+            }
+        }
 
-            let distance = a_star(&grid, &end, &heuristic, &viable, &done);
-            println!("minimum distance backward: {}", distance);
+        let map = route_map(&grid, route.as_ref().map(|route| &route.nodes[..]).unwrap_or(&[]));
+        if has_flag("show-path") {
+            let style = |cell: RouteCell| Some(height_color(cell.height));
+            map.write_to(&mut std::io::stdout(), &RenderOptions{ labels: false, style: Some(&style) }).unwrap();
+        }
+        if let Some(path) = option_value("image") {
+            route_image(&map).save_image(&path).unwrap();
+            println!("Wrote height map with route to {}", path);
         }
     }
-}
\ No newline at end of file
+}
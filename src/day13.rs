@@ -3,7 +3,7 @@ pub mod aoc {
     use std::iter::Peekable;
     use std::cmp::Ordering;
 
-    use crate::util::input_lines;
+    use crate::util::input_sections;
 
     #[derive(Debug, Clone, PartialEq)]
     enum Packet {
@@ -120,33 +120,31 @@ pub mod aoc {
 
     #[allow(dead_code)]
     pub fn day_main() {
-        let packets: Vec<Packet> = input_lines()
-            .filter(|line| !line.is_empty())
-            .map(|line| read_packet(&mut line.chars().peekable()))
+        let pairs: Vec<(Packet, Packet)> = input_sections()
+            .map(|section| {
+                let mut packets = section.lines()
+                    .map(|line| read_packet(&mut line.trim().chars().peekable()));
+                match (packets.next(), packets.next(), packets.next()) {
+                    (Some(left), Some(right), None) => (left, right),
+                    _ => panic!("Expected a pair of packets at line {}", section.first_line() + 1),
+                }
+            })
             .collect();
 
         {
             let mut index_sum = 0;
-            let mut index = 1;
-            let mut maybe_left = None as Option<Packet>;
-            for packet in packets.iter() {
-                match maybe_left {
-                    None => maybe_left = Some(packet.clone()),
-                    Some(left) => {
-                        let order = compare_packets(&left,  &packet);
-                        if order != Ordering::Greater {
-                            index_sum += index;
-                        }
-                        maybe_left = None;
-                        index += 1;
-                    }
+            for (index, (left, right)) in pairs.iter().enumerate() {
+                if compare_packets(left, right) != Ordering::Greater {
+                    index_sum += index + 1;
                 }
             }
             println!("Index sum: {}", index_sum);
         }
 
         {
-            let mut all_packets = packets.clone();
+            let mut all_packets: Vec<Packet> = pairs.iter()
+                .flat_map(|(left, right)| [left.clone(), right.clone()])
+                .collect();
             let p2 = Packet::Many(vec![Packet::Many(vec![Packet::One( 2 )])]);
             let p6 = Packet::Many(vec![Packet::Many(vec![Packet::One( 6 )])]);
             all_packets.push(p2.clone());
@@ -1,9 +1,11 @@
 #[allow(dead_code)]
 pub mod aoc {
 
-    use crate::grid::{Grid, Point, Vector};
+    use crate::grid::{Direction8, InfiniteGrid, Point, Vector, YAxis};
+    use crate::image::{Rgb, ToColor};
     use crate::parser::Parser;
-    use crate::util::input_lines;
+    use crate::record::Recorder;
+    use crate::util::{input_lines, option_value};
 
     #[derive(Debug, Clone, Copy, PartialEq)]
     enum Mat {
@@ -26,6 +28,16 @@ pub mod aoc {
         }
     }
 
+    impl ToColor for Mat {
+        fn to_color(&self) -> Rgb {
+            match self {
+                Mat::Air => Rgb(20, 20, 30),
+                Mat::Rock => Rgb(120, 110, 100),
+                Mat::Sand => Rgb(230, 190, 90),
+            }
+        }
+    }
+
     fn make_parser() -> std::rc::Rc<dyn Parser> {
         use crate::parser::*;
 
@@ -68,8 +80,8 @@ pub mod aoc {
         ).max().unwrap()
     }
 
-    fn grid_from_paths(paths: &Vec<Path>, width: i64, height: i64) -> Grid<Mat> {
-        let mut grid = Grid::new(width, height, Mat::Air);
+    fn grid_from_paths(paths: &Vec<Path>) -> InfiniteGrid<Mat> {
+        let mut grid = InfiniteGrid::new(Mat::Air);
 
         for path in paths {
             let mut from = None as Option<Point>;
@@ -77,18 +89,18 @@ pub mod aoc {
                 from = match from {
                     None => {
                         grid.set(point, Mat::Rock);
-                        Some(point.clone())
+                        Some(*point)
                     },
                     Some(from) => {
                         let dir = (point - &from).signum();
                         assert!(dir.l1_norm() == 1);
-                        let mut step = &from + &dir;
+                        let mut step = from + dir;
                         while &step != point {
                             grid.set(&step, Mat::Rock);
-                            step = &step + &dir;
+                            step += dir;
                         }
                         grid.set(&step, Mat::Rock);
-                        Some(point.clone())
+                        Some(*point)
                     }
                 };
             }
@@ -97,33 +109,35 @@ pub mod aoc {
         grid
     }
 
-    fn add_sand(grid: &mut Grid<Mat>, ingress: &Point) -> bool {
-        let mut position = ingress.clone();
+    // Without a floor, sand falling below `max_depth` is lost to the abyss. With
+    // a floor, it comes to rest two rows below `max_depth` at the latest.
+    fn add_sand(grid: &mut InfiniteGrid<Mat>, ingress: &Point, max_depth: i64, floor: bool) -> bool {
+        let mut position = *ingress;
 
         if grid.get(&position) == Mat::Sand {
             return false;
         }
 
-        const DOWN: Vector = Vector{ xd: 0, yd: 1 };
-        const LEFT: Vector = Vector{ xd: -1, yd: 1 };
-        const RIGHT: Vector = Vector{ xd: 1, yd: 1 };
+        const DOWN: Vector = Direction8::Down.as_vector(YAxis::Down);
+        const LEFT: Vector = Direction8::DownLeft.as_vector(YAxis::Down);
+        const RIGHT: Vector = Direction8::DownRight.as_vector(YAxis::Down);
 
-        fn is_viable(grid: &Grid<Mat>, from: &Point, step: &Vector) -> bool {
+        let is_viable = |grid: &InfiniteGrid<Mat>, from: &Point, step: &Vector| -> bool {
             let new_pos = from + step;
-            if ! grid.is_in_bounds(&new_pos) {
-                return true
+            if floor && new_pos.y == max_depth + 2 {
+                return false
             }
             grid.get(&new_pos) == Mat::Air
-        }
+        };
 
         loop {
             let move_to = {
                 if is_viable(grid, &position, &DOWN) {
-                    Some(&position + &DOWN)
+                    Some(position + DOWN)
                 } else if is_viable(grid, &position, &LEFT) {
-                    Some(&position + &LEFT)
+                    Some(position + LEFT)
                 } else if is_viable(grid, &position, &RIGHT) {
-                    Some(&position + &RIGHT)
+                    Some(position + RIGHT)
                 } else {
                     None
                 }
@@ -131,7 +145,7 @@ pub mod aoc {
             match move_to {
                 None => break,
                 Some(move_to) => {
-                    if ! grid.is_in_bounds(&move_to) {
+                    if !floor && move_to.y > max_depth {
                         return false;
                     }
                     position = move_to
@@ -149,38 +163,48 @@ pub mod aoc {
         let max_depth = max_depth(&paths);
 
         // paths.iter().map(|path| println!("{:?}", path)).count();
-        // grid.dump_part(&Point{ x: 490, y: 0 }, &Direction{ xd: 20, yd: 10 });
 
-        // let mut grid = Grid::new(SAND_INGRESS_X * 2, max_depth + 1, Mat::Air);
+        let sand_ingress = Point{ x: SAND_INGRESS_X, y: 0 };
 
         {
-            let mut grid = grid_from_paths(&paths, SAND_INGRESS_X * 2, max_depth + 1);
-
-            let sand_ingress = Point{ x: SAND_INGRESS_X, y: 0 };
+            let mut grid = grid_from_paths(&paths);
 
             let mut sand_grain_count = 0;
-            while add_sand(&mut grid, &sand_ingress) {
+            while add_sand(&mut grid, &sand_ingress, max_depth, false) {
                 sand_grain_count += 1;
             }
             println!("Able to add {} sand grains without floor", sand_grain_count)
         }
 
         {
-            let mut paths_with_floor = paths.clone();
-            paths_with_floor.push(vec![
-                Point{ x: 0, y: max_depth + 2 },
-                Point{ x: SAND_INGRESS_X * 2, y: max_depth + 2 }
-            ]);
-
-            let mut grid = grid_from_paths(&paths_with_floor, SAND_INGRESS_X * 2 + 1, max_depth + 3);
-
-            let sand_ingress = Point{ x: SAND_INGRESS_X, y: 0 };
+            let mut grid = grid_from_paths(&paths);
+            // Sand resting on the floor spreads out at most as far as it is deep
+            let floor_y = max_depth + 1;
+            let rock_xs = paths.iter().flatten().map(|p| p.x);
+            let min_x = rock_xs.clone().min().unwrap().min(SAND_INGRESS_X - floor_y);
+            let max_x = rock_xs.max().unwrap().max(SAND_INGRESS_X + floor_y);
+            let mut recorder = Recorder::from_args(Mat::Air)
+                .map(|recorder| recorder.bounds(Point{ x: min_x, y: 0 }, Point{ x: max_x, y: floor_y }));
 
             let mut sand_grain_count = 0;
-            while add_sand(&mut grid, &sand_ingress) {
+            while add_sand(&mut grid, &sand_ingress, max_depth, true) {
                 sand_grain_count += 1;
+                if let Some(recorder) = recorder.as_mut() {
+                    recorder.capture_infinite(&grid);
+                }
+            }
+            println!("Able to add {} sand grains with floor", sand_grain_count);
+
+            if let Some(mut recorder) = recorder {
+                recorder.capture_last_infinite(&grid);
+                recorder.finish();
+            }
+
+            if let Some(path) = option_value("image") {
+                let (cave, _) = grid.to_grid().unwrap();
+                cave.save_image(&path).unwrap();
+                println!("Wrote cave to {}", path);
             }
-            println!("Able to add {} sand grains with floor", sand_grain_count)
         }
     }
 }
\ No newline at end of file
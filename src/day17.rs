@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
-use crate::util::input_all;
-use crate::grid::{Grid, Dimensions, Point, Vector};
+use crate::util::{has_flag, input_all};
+use crate::grid::{BitGrid, Grid, InfiniteGrid, Dimensions, Direction4, Point, Vector, YAxis};
+use crate::image::{Rgb, ToColor};
+use crate::record::Recorder;
 
 #[derive(Debug, Clone, Copy)]
 enum Mat {
@@ -18,36 +20,18 @@ impl std::fmt::Display for Mat {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum Direction {
-    Left = 0,
-    Right,
-    Up,
-    Down,
-}
-
-// NOTE: Up is *positive* y delta
-impl Direction {
-    pub const ALL: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
-    const fn as_vector(&self) -> Vector {
-        match self {
-            Direction::Left => Vector{ xd: -1, yd: 0 },
-            Direction::Right => Vector{ xd: 1, yd: 0 },
-            Direction::Up => Vector{ xd: 0, yd: 1 },
-            Direction::Down => Vector{ xd: 0, yd: -1 },
-        }
-    }
-
-    const fn opposite(&self) -> Direction {
+impl ToColor for Mat {
+    fn to_color(&self) -> Rgb {
         match self {
-            Direction::Left => Direction::Right,
-            Direction::Right => Direction::Left,
-            Direction::Up => Direction::Down,
-            Direction::Down => Direction::Up,
+            Mat::Air => Rgb(20, 20, 30),
+            Mat::Rock => Rgb(200, 120, 60),
         }
     }
 }
 
+// The tower grows upwards, so Up is *positive* y delta
+const Y_AXIS: YAxis = YAxis::Up;
+
 struct FixedVec<T, const N: usize> {
     data: [T; N],
     len: usize
@@ -58,7 +42,7 @@ struct FixedVec<T, const N: usize> {
 // instead of calculating which pixels moved. I'm leaving this code in because it's
 // a fun experiment in constant evaluation in rust.
 mod pieces {
-    use super::{Direction, Dimensions, Point, FixedVec};
+    use super::{Direction4, Dimensions, Point, FixedVec, Y_AXIS};
 
     #[derive(Debug, Clone, Copy)]
     pub enum Piece {
@@ -90,7 +74,7 @@ mod pieces {
         ( Dimensions{ w: 2, h: 2 }, &as_points([(0, 1), (1, 1), (0, 0), (1, 0)]) ),
     ];
 
-    const SHIFTED: [[FixedVec<Point, 4>; Direction::ALL.len()]; Piece::ALL.len()] = [
+    const SHIFTED: [[FixedVec<Point, 4>; Direction4::ALL.len()]; Piece::ALL.len()] = [
         shift_data_for_piece(Piece::Horizontal),
         shift_data_for_piece(Piece::Cross),
         shift_data_for_piece(Piece::LeftL),
@@ -106,14 +90,14 @@ mod pieces {
         BASE[piece as usize].1
     }
 
-    pub fn shifted_points(piece: Piece, shift: Direction) -> &'static [Point] {
+    pub fn shifted_points(piece: Piece, shift: Direction4) -> &'static [Point] {
         let vec = &SHIFTED[piece as usize][shift as usize];
         &vec.data[0..vec.len]
     }
 
-    const fn is_shifted_point_occluded(points: &'static [Point], point_idx: usize, direction: Direction) -> bool {
+    const fn is_shifted_point_occluded(points: &'static [Point], point_idx: usize, direction: Direction4) -> bool {
         let unshifted = &points[point_idx];
-        let shift = direction.as_vector();
+        let shift = direction.as_vector(Y_AXIS);
         let shifted = Point{
             x: unshifted.x + shift.xd,
             y: unshifted.y + shift.yd,
@@ -131,14 +115,14 @@ mod pieces {
         occluded
     }
 
-    const fn shift_frontier<const N: usize>(shape: &'static [Point], direction: Direction) -> FixedVec<Point, N> {
+    const fn shift_frontier<const N: usize>(shape: &'static [Point], direction: Direction4) -> FixedVec<Point, N> {
         let mut points: [Point; N] = [Point{ x: 0, y: 0 }; N];
 
         let mut candidate_idx: usize = 0;
         let mut write_idx: usize = 0;
         while candidate_idx < shape.len() {
             let unshifted = &shape[candidate_idx];
-            let shift = direction.as_vector();
+            let shift = direction.as_vector(Y_AXIS);
             let shifted = Point{
                 x: unshifted.x + shift.xd,
                 y: unshifted.y + shift.yd,
@@ -168,10 +152,10 @@ mod pieces {
 
     const fn shift_data_for_piece<const N: usize>(piece: Piece) -> [FixedVec<Point, N>; 4] {
         [
-            shift_frontier::<N>(BASE[piece as usize].1, Direction::Left),
-            shift_frontier::<N>(BASE[piece as usize].1, Direction::Right),
-            shift_frontier::<N>(BASE[piece as usize].1, Direction::Up),
-            shift_frontier::<N>(BASE[piece as usize].1, Direction::Down),
+            shift_frontier::<N>(BASE[piece as usize].1, Direction4::Up),
+            shift_frontier::<N>(BASE[piece as usize].1, Direction4::Right),
+            shift_frontier::<N>(BASE[piece as usize].1, Direction4::Down),
+            shift_frontier::<N>(BASE[piece as usize].1, Direction4::Left),
         ]
     }
 }
@@ -179,13 +163,13 @@ mod pieces {
 use pieces::Piece;
 
 struct TetrisGame {
-    grid: Grid<Mat>,
+    grid: InfiniteGrid<Mat>,
     current_height: i64,
-    jets: Box<dyn Iterator<Item = Direction>>,
+    jets: Box<dyn Iterator<Item = Direction4>>,
     next_piece: Box<dyn Iterator<Item = &'static Piece>>,
 }
 
-fn render_points(grid: &mut Grid<Mat>, material: Mat, points: &[Point], at: &Vector) {
+fn render_points(grid: &mut InfiniteGrid<Mat>, material: Mat, points: &[Point], at: &Vector) {
     for point in points {
         grid.set(&(point + at), material);
     }
@@ -193,19 +177,31 @@ fn render_points(grid: &mut Grid<Mat>, material: Mat, points: &[Point], at: &Vec
 
 const FIELD_WIDTH: i64 = 7;
 
-fn read_game() -> TetrisGame {
-    let mut jets: Vec<Direction> = Vec::new();
+// The walls and the floor count as rock
+fn material_at(grid: &InfiniteGrid<Mat>, point: &Point) -> Mat {
+    if point.x < 0 || point.x >= FIELD_WIDTH || point.y < 0 {
+        Mat::Rock
+    } else {
+        grid.get(point)
+    }
+}
+
+fn read_jets() -> Vec<Direction4> {
+    let mut jets: Vec<Direction4> = Vec::new();
     for char in input_all().chars() {
         match char {
-            '<' => jets.push(Direction::Left),
-            '>' => jets.push(Direction::Right),
+            '<' => jets.push(Direction4::Left),
+            '>' => jets.push(Direction4::Right),
             ch if ch.is_whitespace() => (),
             ch => panic!("unexpected input: '{}'", ch),
         }
     }
+    jets
+}
 
+fn new_game(jets: Vec<Direction4>) -> TetrisGame {
     TetrisGame{
-        grid: Grid::new(FIELD_WIDTH, 2022 * 4, Mat::Air),
+        grid: InfiniteGrid::new(Mat::Air),
         current_height: 0,
         jets: Box::new(jets.into_iter().cycle()),
         next_piece: Box::new(Piece::ALL.iter().cycle()),
@@ -215,7 +211,9 @@ fn read_game() -> TetrisGame {
 fn show_pieces(with_directions: bool) {
     fn display_points(points: &[Point]) {
         let mut grid: Grid<Mat> = Grid::new(4, 4, Mat::Air);
-        render_points(&mut grid, Mat::Rock, points, &Vector{ xd: 0, yd: 0 });
+        for point in points {
+            grid.set(point, Mat::Rock);
+        }
         grid.dump_part(&Point{ x: 0, y: 3 }, &Vector{ xd: 4, yd: -4 });
     }
 
@@ -226,27 +224,27 @@ fn show_pieces(with_directions: bool) {
         if ! with_directions {
             continue
         }
-        for direction in Direction::ALL {
+        for direction in Direction4::ALL {
             println!("{:?} -> {:?}", piece, direction);
             let points: Vec<Point> = pieces::shifted_points(piece, direction).iter()
-                .map(|p| p - &direction.as_vector()).collect();
+                .map(|p| p - &direction.as_vector(Y_AXIS)).collect();
             display_points(&points[..]);
         }
     }
 }
 
-fn push_piece(grid: &mut Grid<Mat>, piece: Piece, current_position: Point, direction: Direction) -> bool {
+fn push_piece(grid: &mut InfiniteGrid<Mat>, piece: Piece, current_position: Point, direction: Direction4) -> bool {
     let pos_vector = current_position.as_vector();
     for new_point in pieces::shifted_points(piece, direction) {
         let new_point = &(new_point + &pos_vector);
-        match grid.get_or_default(new_point, Mat::Rock) {
+        match material_at(grid, new_point) {
             Mat::Rock => return false,
             Mat::Air => (),
         }
     }
     for old_point in pieces::shifted_points(piece, direction.opposite()) {
         let old_point = &(old_point + &pos_vector);
-        let old_point = &(old_point + &direction.as_vector());
+        let old_point = &(old_point + &direction.as_vector(Y_AXIS));
         grid.set(old_point, Mat::Air);
     }
     for new_point in pieces::shifted_points(piece, direction) {
@@ -270,11 +268,11 @@ fn simulate_piece(game: &mut TetrisGame) {
         let jet = game.jets.next().unwrap();
 
         if push_piece(&mut game.grid, *piece, position, jet) {
-            position = &position + &jet.as_vector();
+            position += jet.as_vector(Y_AXIS);
         }
 
-        if push_piece(&mut game.grid, *piece, position, Direction::Down) {
-            position = &position + &Direction::Down.as_vector();
+        if push_piece(&mut game.grid, *piece, position, Direction4::Down) {
+            position += Direction4::Down.as_vector(Y_AXIS);
         } else {
             break;
         }
@@ -283,6 +281,89 @@ fn simulate_piece(game: &mut TetrisGame) {
     game.current_height = std::cmp::max(game.current_height, position.y + pieces::dims(*piece).h);
 }
 
+// Each row of the piece as a bitmask, bottom row first
+fn piece_rows(piece: Piece) -> Vec<u64> {
+    let mut rows = vec![0; pieces::dims(piece).h as usize];
+    for point in pieces::points(piece) {
+        rows[point.y as usize] |= 1 << point.x;
+    }
+    rows
+}
+
+// Same rules as `simulate_piece`, but with the tower as a bit grid so every
+// collision check is one AND per piece row
+fn drop_piece_bits<Jets: Iterator<Item = Direction4>>(tower: &mut BitGrid, rows: &[u64], jets: &mut Jets) {
+    let mut position = Point{ x: 2, y: tower.height() + 3 };
+    loop {
+        let jet = jets.next().unwrap();
+        let pushed = position + jet.as_vector(Y_AXIS);
+        if !tower.collides(rows, &pushed) {
+            position = pushed;
+        }
+
+        let fallen = position + Direction4::Down.as_vector(Y_AXIS);
+        if tower.collides(rows, &fallen) {
+            break;
+        }
+        position = fallen;
+    }
+    tower.place(rows, &position);
+}
+
+// Rows of the tower shown when recording, the top of it scrolling upwards
+const RECORD_ROWS: i64 = 40;
+
+// The top `rows` rows of the tower, fewer while it is lower than that
+fn tower_frame(tower: &BitGrid, rows: i64) -> Grid<Mat> {
+    let bottom = (tower.height() - rows).max(0);
+    let mut frame = Grid::new(tower.width(), tower.height() - bottom, Mat::Air);
+    for p in frame.points() {
+        if tower.get(&Point{ x: p.x, y: p.y + bottom }) {
+            frame.set(&p, Mat::Rock);
+        }
+    }
+    frame
+}
+
+fn tower_height_bits(jets: &[Direction4], rounds: usize, recorder: &mut Option<Recorder<Mat>>) -> i64 {
+    let rows: Vec<Vec<u64>> = Piece::ALL.iter().map(|piece| piece_rows(*piece)).collect();
+    let mut jets = jets.iter().copied().cycle();
+    let mut tower = BitGrid::new(FIELD_WIDTH);
+    for rows in rows.iter().cycle().take(rounds) {
+        drop_piece_bits(&mut tower, rows, &mut jets);
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture(&tower_frame(&tower, RECORD_ROWS));
+        }
+    }
+    if let Some(recorder) = recorder.as_mut() {
+        recorder.capture_last_at(&tower_frame(&tower, RECORD_ROWS), Point{ x: 0, y: 0 });
+    }
+    tower.height()
+}
+
+fn tower_height_enum(jets: &[Direction4], rounds: usize) -> i64 {
+    let mut game = new_game(jets.to_vec());
+    for _ in 0..rounds {
+        simulate_piece(&mut game);
+    }
+    game.current_height
+}
+
+// Times the enum grid with `push_piece` against the bit grid
+fn bench(jets: &[Direction4], rounds: usize) {
+    let start = std::time::Instant::now();
+    let enum_height = tower_height_enum(jets, rounds);
+    let enum_time = start.elapsed();
+
+    let start = std::time::Instant::now();
+    let bits_height = tower_height_bits(jets, rounds, &mut None);
+    let bits_time = start.elapsed();
+
+    assert_eq!(enum_height, bits_height, "Enum and bit grid towers differ");
+    println!("{} rounds: enum grid {:?}, bit grid {:?} ({:.1}x)", rounds, enum_time, bits_time,
+        enum_time.as_secs_f64() / bits_time.as_secs_f64());
+}
+
 pub fn day_main() {
     // show_pieces(false);
 
@@ -298,10 +379,10 @@ pub fn day_main() {
     // grid.dump_part_default(&Point{ x: -1, y: 9 }, &Vector{ xd: 9, yd: -11 }, Mat::Rock);
 
 
-    // for direction in [Direction::Left, Direction::Left, Direction::Left] {
+    // for direction in [Direction4::Left, Direction4::Left, Direction4::Left] {
 
     //     if push_piece(&mut grid, current_piece, current_pos.clone(), direction) {
-    //         current_pos = &current_pos + &direction.as_vector();
+    //         current_pos = &current_pos + &direction.as_vector(Y_AXIS);
     //     }
 
     //     step += 1;
@@ -310,18 +391,26 @@ pub fn day_main() {
     //     grid.dump_part_default(&Point{ x: -1, y: 9 }, &Vector{ xd: 9, yd: -11 }, Mat::Rock);
     // }
 
-    let mut game = read_game();
+    let jets = read_jets();
 
     // const ROUNDS: usize = 1_000_000_000_000;
     const ROUNDS: usize = 2022;
     // const ROUNDS: usize = 10;
 
-    for _ in 0..ROUNDS {
-        simulate_piece(&mut game);
-        // println!("");
-        // let display_height = game.current_height + 3;
-        // game.grid.dump_part_default(&Point{ x: -1, y: display_height }, &Vector{ xd: 9, yd: -display_height - 2}, Mat::Rock);
+    if has_flag("bench") {
+        bench(&jets, ROUNDS);
+        return;
     }
-    println!("Tower height after {} rounds: {}", ROUNDS, game.current_height);
 
-}
\ No newline at end of file
+    // The top of the tower after every piece, growing upwards
+    let mut recorder = Recorder::from_args(Mat::Air).map(|recorder| recorder
+        .y_up()
+        .bounds(Point{ x: 0, y: 0 }, Point{ x: FIELD_WIDTH - 1, y: RECORD_ROWS - 1 }));
+
+    let height = tower_height_bits(&jets, ROUNDS, &mut recorder);
+    println!("Tower height after {} rounds: {}", ROUNDS, height);
+
+    if let Some(recorder) = recorder {
+        recorder.finish();
+    }
+}
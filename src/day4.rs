@@ -1,80 +1,43 @@
 pub mod aoc {
 
-    #[derive(Debug)]
-    struct Section {
-        start: u32,
-        end: u32,
-    }
+    use std::ops::Range;
 
-    fn contains(outer: &Section, inner: &Section) -> bool {
-        outer.start <= inner.start && outer.end >= inner.end
-    }
+    use crate::interval::{range_contains, ranges_overlap};
+    use crate::util::{expect_line, parse_lines_with};
 
-    fn overlap(a: &Section, b: &Section) -> bool {
-        let min = |x, y| if x < y { x } else { y };
-        let max = |x, y| if x > y { x } else { y };
-        max(a.start, b.start) <= min(a.end, b.end)
+    // Sections are given inclusively ("2-4"), but stored as half-open ranges
+    type Section = Range<u32>;
+
+    fn parse_section(text: &str) -> Result<Section, String> {
+        let (start, end) = text.split_once("-")
+            .ok_or_else(|| format!("Expected '<start>-<end>', but got '{}'", text))?;
+        let start = start.parse::<u32>().map_err(|error| error.to_string())?;
+        let end = end.parse::<u32>().map_err(|error| error.to_string())?;
+        let end = end.checked_add(1).ok_or_else(|| format!("Section end {} is too large", end))?;
+        Ok(start..end)
     }
 
-    fn parse_line_p1(line: &str) -> Option<(Section, Section)> {
-        let (left, right) = line.split_once(",")?;
-        let (left_start, left_end) = left.split_once("-")?;
-        let (right_start, right_end) = right.split_once("-")?;
-        return Some((
-            Section{
-                start: left_start.parse::<u32>().ok()?,
-                end: left_end.parse::<u32>().ok()?,
-            },
-            Section{
-                start: right_start.parse::<u32>().ok()?,
-                end: right_end.parse::<u32>().ok()?,
-            }
-        ))
+    fn parse_line_p1(line: &str) -> Result<(Section, Section), String> {
+        let (left, right) = line.split_once(",")
+            .ok_or_else(|| format!("Expected two comma separated sections, but got '{}'", line))?;
+        Ok((parse_section(left)?, parse_section(right)?))
     }
 
     #[allow(dead_code)]
     pub fn day_main_part1() {
-        let on_line = |line: &str, acc: u32| -> u32 {
-            if line.is_empty() {
-                return acc;
-            }
-            let (left, right) = match parse_line_p1(line) {
-                None => { return 0 }
-                Some((left, right)) => (left, right),
-            };
-            if contains(&left, &right) || contains(&right, &left) {
-                acc + 1
-            } else {
-                acc
-            }
-        };
-
-        let on_done = std::convert::identity;
-
-        let result = crate::util::run_on_input(0, on_line, on_done);
+        let result = parse_lines_with(parse_line_p1)
+            .map(expect_line)
+            .filter(|(left, right)| range_contains(left, right) || range_contains(right, left))
+            .count();
         println!("overlapping sections part 1: {}", result);
     }
 
     #[allow(dead_code)]
     pub fn day_main_part2() {
-        let on_line = |line: &str, acc: u32| -> u32 {
-            if line.is_empty() {
-                return acc;
-            }
-            let (left, right) = match parse_line_p1(line) {
-                None => { return 0 }
-                Some((left, right)) => (left, right),
-            };
-            if overlap(&left, &right) {
-                acc + 1
-            } else {
-                acc
-            }
-        };
-
-        let on_done = std::convert::identity;
-
-        let result = crate::util::run_on_input(0, on_line, on_done);
+        let result = parse_lines_with(parse_line_p1)
+            .map(expect_line)
+            .filter(|(left, right)| ranges_overlap(left, right))
+            .count();
         println!("overlapping sections part 2: {}", result);
     }
 
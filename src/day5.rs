@@ -2,6 +2,8 @@ pub mod aoc {
 
     use std::collections::VecDeque;
 
+    use crate::util::input_sections;
+
     #[derive(Debug)]
     struct Cargo<const N: usize> {
         stacks: [VecDeque<char>; N],
@@ -46,7 +48,7 @@ pub mod aoc {
     }
 
     fn parse_line_stacks<const N: usize>(line: &str, cargo: &mut Cargo<N>) -> bool {
-        if str_at(line, 0) != Some('[') {
+        if !line.trim_start().starts_with('[') {
             return false;
         }
         for stack_idx in 0..N {
@@ -132,44 +134,37 @@ pub mod aoc {
         String::from_utf8(buffer).unwrap()
     }
 
+    // The input starts with the stack drawing, followed by the move commands
+    fn run_input<const N: usize, CommandFn>(run_command: CommandFn) -> Cargo<N>
+    where
+        CommandFn: Fn(&str, &mut Cargo<N>)
+    {
+        let mut sections = input_sections();
+        let mut cargo = Cargo::<N>::new();
+        let drawing = sections.next().expect("Expected stack drawing");
+        for line in drawing.lines() {
+            parse_line_stacks(line, &mut cargo);
+        }
+        for section in sections {
+            for line in section.lines() {
+                run_command(line, &mut cargo);
+            }
+        }
+        cargo
+    }
+
     const NUM_STACKS: usize = 9;
 
     #[allow(dead_code)]
     pub fn day_main_part1() {
-        let on_line = |line: &str, mut cargo: Cargo<NUM_STACKS>| -> Cargo<NUM_STACKS> {
-            if line.is_empty() {
-                return cargo;
-            }
-            if parse_line_stacks(line, &mut cargo) {
-                return cargo;
-            }
-            run_line_command_1(line, &mut cargo);
-            cargo
-        };
-
-        let on_done = std::convert::identity;
-
-        let result = crate::util::run_on_input(Cargo::<NUM_STACKS>::new(), on_line, on_done);
+        let result = run_input::<NUM_STACKS, _>(run_line_command_1);
         dump(&result);
         println!("Top crates part 1: {}", get_top_crates(&result));
     }
 
     #[allow(dead_code)]
     pub fn day_main_part2() {
-        let on_line = |line: &str, mut cargo: Cargo<NUM_STACKS>| -> Cargo<NUM_STACKS> {
-            if line.is_empty() {
-                return cargo;
-            }
-            if parse_line_stacks(line, &mut cargo) {
-                return cargo;
-            }
-            run_line_command_2(line, &mut cargo);
-            cargo
-        };
-
-        let on_done = std::convert::identity;
-
-        let result = crate::util::run_on_input(Cargo::<NUM_STACKS>::new(), on_line, on_done);
+        let result = run_input::<NUM_STACKS, _>(run_line_command_2);
         dump(&result);
         println!("Top crates part 2: {}", get_top_crates(&result));
     }
@@ -1,113 +1,27 @@
 pub mod aoc {
-    use crate::util::input_lines_indices;
+    use crate::grid::{Grid, Point, Vector};
+    use crate::image::heat;
+    use crate::util::{input_lines_indices, option_value};
     use std::collections::HashSet;
 
     type TreeType = i32;
 
-    struct SquareBuf {
-        buf: Vec<TreeType>,
-        size: usize,
-    }
-
-    struct SquareBufIterator<'a> {
-        buf: &'a SquareBuf,
-        x: i32,
-        y: i32,
-        x_step: i8,
-        y_step: i8,
-    }
-
-    impl <'a> Iterator for SquareBufIterator<'a> {
-        type Item = (usize, usize, TreeType);
-
-        fn next(&mut self) -> Option<Self::Item> {
-            let size = self.buf.size as i32;
-            if self.x < 0 || self.x >= size || self.y < 0 || self.y >= size {
-                return None;
-            }
-            let result = Some((
-                self.x as usize,
-                self.y as usize,
-                (self.buf.get(self.x as usize, self.y as usize))
-            ));
-            self.x += self.x_step as i32;
-            self.y += self.y_step as i32;
-            result
-        }
-    }
-
-    impl SquareBuf {
-        fn new(init: TreeType, size: usize) -> SquareBuf {
-            SquareBuf { buf: vec![init; size * size], size: size }
-        }
-
-        fn get(&self, x: usize, y: usize) -> TreeType {
-            assert!(x < self.size);
-            assert!(y < self.size);
-            self.buf[x + y * self.size]
-        }
-
-        fn set(&mut self, x: usize, y: usize, value: TreeType) {
-            assert!(x < self.size);
-            assert!(y < self.size);
-            self.buf[x + y * self.size] = value;
-        }
-
-        fn row_iter<'a>(&'a self, y: usize, x_step: i8) -> SquareBufIterator<'a> {
-            SquareBufIterator{
-                buf: self,
-                x: if x_step > 0 { 0 } else { (self.size-1) as i32 },
-                y: y as i32,
-                x_step: x_step,
-                y_step: 0,
-            }
-        }
-
-        fn col_iter<'a>(&'a self, x: usize, y_step: i8) -> SquareBufIterator<'a> {
-            SquareBufIterator{
-                buf: self,
-                x: x as i32,
-                y: if y_step > 0 { 0 } else { (self.size-1) as i32 },
-                x_step: 0,
-                y_step: y_step,
-            }
-        }
-
-        #[allow(dead_code)]
-        fn dump(&self) -> () {
-            for row in 0..self.size {
-                for col in 0..self.size {
-                    print!("{}", self.get(col, row));
-                }
-                println!("");
-            }
-        }
-    }
-
-    fn read_trees() -> SquareBuf {
-        let mut maybe_buf: Option<SquareBuf> = None;
-        for (row, line) in input_lines_indices() {
-            if maybe_buf.is_none() {
-                maybe_buf = Some(SquareBuf::new(-1, line.len()));
-            }
-            let buf = maybe_buf.as_mut().unwrap();
-            for (col, ch) in line.char_indices() {
-                    assert!(ch >= '0' && ch <= '9');
-                    let value = ch as i32 - '0' as i32;
-                    buf.set(col, row, value);
-            }
-        }
-        maybe_buf.unwrap()
+    fn read_trees() -> Grid<TreeType> {
+        let parse_height = |ch: char, _| ch.to_digit(10)
+            .map(|digit| digit as TreeType)
+            .ok_or_else(|| format!("Expected tree height 0-9, but got '{}'", ch));
+        Grid::from_lines(input_lines_indices(), parse_height)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
     struct State {
         highest: TreeType,
-        visible: HashSet<(usize, usize)>,
+        visible: HashSet<Point>,
     }
-    fn count_increasing(mut state: State, value: (usize, usize, TreeType)) -> State {
-        let (x, y, height) = value;
+    fn count_increasing(mut state: State, value: (Point, TreeType)) -> State {
+        let (point, height) = value;
         if height > state.highest {
-            state.visible.insert((x, y));
+            state.visible.insert(point);
             State{
                 highest: height,
                 visible: state.visible,
@@ -117,27 +31,22 @@ pub mod aoc {
         }
     }
 
-    fn scenic_score(forest: &SquareBuf, x: usize, y: usize) -> usize {
-        let distance = |x_step: i32, y_step: i32| -> usize{
-            let height = forest.get(x, y);
-            let mut x = x as i32 + x_step;
-            let mut y = y as i32 + y_step;
-            let mut steps = 1;
-            while x >= 0 && x < forest.size as i32 && y >= 0 && y < forest.size as i32 {
+    fn scenic_score(forest: &Grid<TreeType>, at: &Point) -> usize {
+        let height = forest.get(at);
+        let distance = |step: Vector| -> usize {
+            let mut steps = 0;
+            for (_, tree) in forest.ray(at, step) {
                 steps += 1;
-                if forest.get(x as usize, y as usize) >= height {
+                if tree >= height {
                     break;
                 }
-                x += x_step;
-                y += y_step;
-
             }
-            steps - 1
+            steps
         };
-        let left = distance(-1, 0);
-        let right = distance(1, 0);
-        let up = distance(0, -1);
-        let down = distance(0, 1);
+        let left = distance(Vector{ xd: -1, yd: 0 });
+        let right = distance(Vector{ xd: 1, yd: 0 });
+        let up = distance(Vector{ xd: 0, yd: -1 });
+        let down = distance(Vector{ xd: 0, yd: 1 });
         left * right * up * down
     }
 
@@ -151,29 +60,37 @@ pub mod aoc {
             highest: -1,
             visible: HashSet::new(),
         };
-        for idx in 0..trees.size {
-            state.highest = -1;
-            state = trees.row_iter(idx, 1).fold(state, count_increasing);
-            state.highest = -1;
-            state = trees.row_iter(idx, -1).fold(state, count_increasing);
-            state.highest = -1;
-            state = trees.col_iter(idx, 1).fold(state, count_increasing);
-            state.highest = -1;
-            state = trees.col_iter(idx, -1).fold(state, count_increasing);
+        // Looking in from the left of each rotation covers all four sides
+        let mut view = trees.as_view();
+        for _ in 0..4 {
+            for row in 0..view.height() {
+                state.highest = -1;
+                state = view.row(row)
+                    .map(|(point, height)| (view.grid_point(&point), height))
+                    .fold(state, count_increasing);
+            }
+            view = view.rotate90();
         }
 
         println!("visible from outside: {}", state.visible.len());
 
+        let mut scores = Grid::new(trees.width(), trees.height(), 0);
         let mut max_score = 0;
-        for row in 0..trees.size {
-            for col in 0..trees.size {
-                let score = scenic_score(&trees, col, row);
-                if score > max_score {
-                    max_score = score;
-                }
+        for point in trees.points() {
+            let score = scenic_score(&trees, &point);
+            scores.set(&point, score);
+            if score > max_score {
+                max_score = score;
             }
         }
         println!("highest scenic score: {}", max_score);
+
+        if let Some(path) = option_value("image") {
+            // Scores span several orders of magnitude, so the heat map is logarithmic
+            let log_score = |score: usize| (score as f64 + 1.0).ln();
+            scores.save_image_with(&path, |score| heat(log_score(score), 0.0, log_score(max_score))).unwrap();
+            println!("Wrote scenic score heat map to {}", path);
+        }
     }
 
     #[allow(non_upper_case_globals, dead_code)]
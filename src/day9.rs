@@ -1,77 +1,34 @@
 pub mod aoc {
-    use std::ops::{Add, Sub};
     use std::collections::HashSet;
-
-    use crate::util::input_lines;
-
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-    struct Point {
-        x: i32,
-        y: i32,
-    }
-
-    #[derive(Debug)]
-    struct Delta {
-        xd: i32,
-        yd: i32,
-    }
-
-    impl <'a, 'b> Add<&'b Delta> for &'a Point {
-        type Output = Point;
-
-        fn add(self, rhs: &'b Delta) -> Self::Output {
-            Point{
-                x: self.x + rhs.xd,
-                y: self.y + rhs.yd,
-            }
-        }
-    }
-
-    impl Add<Delta> for Point {
-        type Output = Point;
-
-        fn add(self, rhs: Delta) -> Self::Output {
-            Point{
-                x: (&self).x + (&rhs).xd,
-                y: (&self).y + (&rhs).yd,
-            }
-        }
-    }
-
-    impl <'a, 'b> Sub<&'b Point> for &'a Point {
-        type Output = Delta;
-
-        fn sub(self, rhs: &'b Point) -> Self::Output {
-            Delta{
-                xd: self.x - rhs.x,
-                yd: self.y - rhs.y,
-            }
-        }
-    }
-
-    fn parse_line(line: &str) -> Delta {
-        let (dir, steps) = line.split_once(' ').unwrap();
-        let direction = match dir {
-            "R" => (1, 0),
-            "L" => (-1, 0),
-            "U" => (0, -1),
-            "D" => (0, 1),
-            x => panic!("unexpected direction: {:?}", x)
-        };
-        let multiplier = steps.parse::<i32>().unwrap();
-        Delta{
-            xd: direction.0 * multiplier,
-            yd: direction.1 * multiplier,
-        }
-    }
-
-    fn signum(x: i32) -> i32 {
-        if x < 0 {
-            -1
-        } else if x > 0 {
-            1
-        } else {
-            0
+    use std::str::FromStr;
+
+    use crate::grid::{self, Direction4, YAxis};
+    use crate::record::Recorder;
+    use crate::util::{expect_line, parse_lines};
+
+    type Point = grid::Point<i32>;
+    type Delta = grid::Vector<i32>;
+
+    struct Move(Delta);
+
+    impl FromStr for Move {
+        type Err = String;
+
+        fn from_str(line: &str) -> Result<Self, Self::Err> {
+            let (dir, steps) = line.split_once(' ')
+                .ok_or_else(|| format!("Expected '<direction> <steps>', but got '{}'", line))?;
+            let direction = match dir {
+                "R" => Direction4::Right,
+                "L" => Direction4::Left,
+                "U" => Direction4::Up,
+                "D" => Direction4::Down,
+                _ => return Err(format!("Expected direction R, L, U or D, but got '{}'", dir)),
+            }.as_vector(YAxis::Down);
+            let multiplier = steps.parse::<i32>().map_err(|error| error.to_string())?;
+            Ok(Move(Delta{
+                xd: direction.xd as i32 * multiplier,
+                yd: direction.yd as i32 * multiplier,
+            }))
         }
     }
 
@@ -81,10 +38,7 @@ pub mod aoc {
         if dist.xd.abs() < 2 && dist.yd.abs() < 2 {
             return None;
         }
-        Some(Delta{
-            xd: signum(dist.xd),
-            yd: signum(dist.yd),
-        })
+        Some(dist.signum())
     }
 
     #[allow(dead_code)]
@@ -93,31 +47,48 @@ pub mod aoc {
         if dist.xd == 0 && dist.yd == 0 {
             return None;
         }
-        Some(Delta{
-            xd: signum(dist.xd),
-            yd: signum(dist.yd),
-        })
+        Some(dist.signum())
     }
 
-    fn simulate_move<const KNOT_COUNT: usize>(knots: &mut [Point; KNOT_COUNT], inst: &Delta) -> Vec<Point> {
+    // Frame showing the start as 's', the head as 'H' and the other knots by
+    // their index, covering just the knots and placed where they are
+    fn capture_knots(recorder: &mut Recorder<char>, knots: &[Point]) {
+        let start = Point{ x: 0, y: 0 };
+        let min = knots.iter().chain([&start]).fold(start, |min, p| Point{ x: min.x.min(p.x), y: min.y.min(p.y) });
+        let max = knots.iter().chain([&start]).fold(start, |max, p| Point{ x: max.x.max(p.x), y: max.y.max(p.y) });
+        let mut frame = grid::Grid::new((max.x - min.x + 1) as i64, (max.y - min.y + 1) as i64, '.');
+        frame.set(&(start - min.as_vector()), 's');
+        for (i, knot) in knots.iter().enumerate().rev() {
+            let label = if i == 0 { 'H' } else { char::from_digit(i as u32, 36).unwrap() };
+            frame.set(&(knot - &min.as_vector()), label);
+        }
+        recorder.capture_at(&frame, grid::Point{ x: min.x as i64, y: min.y as i64 });
+    }
+
+    fn simulate_move<const KNOT_COUNT: usize>(knots: &mut [Point; KNOT_COUNT], inst: &Delta,
+        recorder: &mut Option<Recorder<char>>) -> Vec<Point>
+    {
         assert!(KNOT_COUNT > 1);
 
         let mut tail_positions: Vec<Point> = Vec::new();
         let head_goal = &knots[0] + inst;
         while let Some(head_step) = calculate_head_move(&knots[0], &head_goal) {
-            let new_head = &knots[0] + &head_step;
+            let new_head = knots[0] + head_step;
             // println!("  moved knot [{}] {:?} by {:?} to {:?}", 0, knots[0], head_step, new_head);
             knots[0] = new_head;
             for i in 1..KNOT_COUNT {
                 if let Some(knot_step) = calculate_tail_move(&knots[i], &knots[i-1]) {
-                    let new_knot = &knots[i] + &knot_step;
+                    let new_knot = knots[i] + knot_step;
                     // println!("  moved knot [{}] {:?} by {:?} to {:?}", i, knots[i], head_step, new_head);
                     if i == KNOT_COUNT - 1 {
-                        tail_positions.push(new_knot.clone());
+                        tail_positions.push(new_knot);
                     }
                     knots[i] = new_knot;
                 }
             }
+            if let Some(recorder) = recorder {
+                capture_knots(recorder, &knots[..]);
+            }
         }
         tail_positions
     }
@@ -132,23 +103,50 @@ pub mod aoc {
         let mut knots_part2 = [(); 10].map(|_| Point{ x: 0, y: 0});
         tail_positions_part2.insert(Point{ x: 0, y: 0 });
 
-        for line in input_lines() {
-            if line.is_empty() {
-                continue;
-            }
-            let total_move = parse_line(&line);
-            for point in simulate_move(&mut knots_part1, &total_move) {
+        let moves: Vec<Move> = parse_lines::<Move>().map(expect_line).collect();
+
+        // Knots never leave the box the head moved in, which makes the canvas
+        let mut head = Point{ x: 0, y: 0 };
+        let (mut min, mut max) = (head, head);
+        for Move(total_move) in moves.iter() {
+            head += *total_move;
+            min = Point{ x: min.x.min(head.x), y: min.y.min(head.y) };
+            max = Point{ x: max.x.max(head.x), y: max.y.max(head.y) };
+        }
+        let to_canvas = |p: Point| grid::Point{ x: p.x as i64, y: p.y as i64 };
+        let mut recorder = Recorder::from_args('.')
+            .map(|recorder| recorder.bounds(to_canvas(min), to_canvas(max)));
+
+        for Move(total_move) in moves.iter() {
+            for point in simulate_move(&mut knots_part1, total_move, &mut None) {
                 tail_positions_part1.insert(point);
             }
 
-            for point in simulate_move(&mut knots_part2, &total_move) {
+            for point in simulate_move(&mut knots_part2, total_move, &mut recorder) {
                 tail_positions_part2.insert(point);
             }
         }
         println!("total positions visited by tail 1: {}", tail_positions_part1.len());
         println!("total positions visited by tail 2: {}", tail_positions_part2.len());
+
+        if let Some(recorder) = recorder {
+            recorder.finish();
+        }
     }
 
     #[allow(non_upper_case_globals, dead_code)]
     pub const day_main: fn() = day_main_part;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_move() {
+            assert_eq!("U 3".parse::<Move>().map(|Move(delta)| delta), Ok(Delta{ xd: 0, yd: -3 }));
+            assert_eq!("L 2".parse::<Move>().map(|Move(delta)| delta), Ok(Delta{ xd: -2, yd: 0 }));
+            assert!("N 1".parse::<Move>().is_err());
+            assert!("> 1".parse::<Move>().is_err());
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,351 @@
+#![allow(dead_code)]
+
+use std::fmt::Write;
+
+use crate::parser::{Grammar, Parser};
+
+// Splits a grammar into rules, one per named parser (see `parser::make_named`).
+// The root comes first and is called "start" unless it is named itself.
+pub fn collect_rules(parser: &dyn Parser) -> Vec<(String, Grammar)> {
+    fn visit(grammar: &Grammar, rules: &mut Vec<(String, Grammar)>) {
+        match grammar {
+            Grammar::Char(_) | Grammar::Range(_, _) | Grammar::AlphaNum | Grammar::Lit(_) => (),
+            Grammar::Alt(items) | Grammar::Seq(items) => {
+                for item in items {
+                    visit(item, rules);
+                }
+            },
+            Grammar::Capture(value) | Grammar::Opt(value) | Grammar::Any(value) | Grammar::Many(value) => {
+                visit(value, rules)
+            },
+            Grammar::List(element, separator) => {
+                visit(element, rules);
+                visit(separator, rules);
+            },
+            Grammar::Rule(name, body) => {
+                if rules.iter().any(|(known, _)| known == name) {
+                    return;
+                }
+                rules.push((name.clone(), (**body).clone()));
+                visit(body, rules);
+            },
+        }
+    }
+
+    let mut rules = Vec::new();
+    match parser.grammar() {
+        rule @ Grammar::Rule(_, _) => visit(&rule, &mut rules),
+        body => {
+            rules.push(("start".to_owned(), body.clone()));
+            visit(&body, &mut rules);
+        }
+    }
+    rules
+}
+
+fn uses_alphanum(grammar: &Grammar) -> bool {
+    match grammar {
+        Grammar::AlphaNum => true,
+        Grammar::Char(_) | Grammar::Range(_, _) | Grammar::Lit(_) | Grammar::Rule(_, _) => false,
+        Grammar::Alt(items) | Grammar::Seq(items) => items.iter().any(uses_alphanum),
+        Grammar::Capture(value) | Grammar::Opt(value) | Grammar::Any(value) | Grammar::Many(value) =>
+            uses_alphanum(value),
+        Grammar::List(element, separator) => uses_alphanum(element) || uses_alphanum(separator),
+    }
+}
+
+pub fn to_ebnf(parser: &dyn Parser) -> String {
+    let rules = collect_rules(parser);
+    let alphanum = rules.iter().any(|(_, body)| uses_alphanum(body));
+    let width = rules.iter().map(|(name, _)| name.len())
+        .chain(alphanum.then_some("ALPHANUM".len()))
+        .max().unwrap_or(0);
+    let mut result = String::new();
+    for (name, body) in rules.iter() {
+        writeln!(result, "{:width$} ::= {}", name, body, width = width).unwrap();
+    }
+    if alphanum {
+        writeln!(result, "{:width$} ::= /* any Unicode alphanumeric character */", "ALPHANUM", width = width).unwrap();
+    }
+    result
+}
+
+const CHAR_WIDTH: i64 = 8;
+const BOX_HEIGHT: i64 = 22;
+const GAP_H: i64 = 10;
+const GAP_V: i64 = 10;
+const ARC: i64 = 10;
+const GROUP_PAD: i64 = 8;
+const GROUP_LABEL: i64 = 14;
+
+// Railroad diagram node. Every node is laid out along a horizontal track, with
+// `up` and `down` being its extent above and below that track.
+enum Diagram {
+    Skip,
+    Terminal(String),
+    NonTerminal(String),
+    Seq(Vec<Diagram>),
+    Choice(Vec<Diagram>),
+    Loop(Box<Diagram>, Box<Diagram>),
+    Group(Box<Diagram>, String),
+}
+
+struct Size {
+    w: i64,
+    up: i64,
+    down: i64,
+}
+
+impl Diagram {
+    fn from_grammar(grammar: &Grammar) -> Diagram {
+        match grammar {
+            Grammar::Char(_) | Grammar::Range(_, _) | Grammar::Lit(_) => Diagram::Terminal(grammar.to_string()),
+            Grammar::AlphaNum => Diagram::NonTerminal("ALPHANUM".to_owned()),
+            Grammar::Alt(items) => Diagram::Choice(items.iter().map(Diagram::from_grammar).collect()),
+            Grammar::Seq(items) => Diagram::Seq(items.iter().map(Diagram::from_grammar).collect()),
+            Grammar::Capture(value) =>
+                Diagram::Group(Box::new(Diagram::from_grammar(value)), "capture".to_owned()),
+            Grammar::Opt(value) => Diagram::Choice(vec![Diagram::Skip, Diagram::from_grammar(value)]),
+            Grammar::Any(value) => Diagram::Choice(vec![
+                Diagram::Skip,
+                Diagram::Loop(Box::new(Diagram::from_grammar(value)), Box::new(Diagram::Skip)),
+            ]),
+            Grammar::Many(value) =>
+                Diagram::Loop(Box::new(Diagram::from_grammar(value)), Box::new(Diagram::Skip)),
+            Grammar::List(element, separator) => Diagram::Loop(
+                Box::new(Diagram::from_grammar(element)),
+                Box::new(Diagram::from_grammar(separator))
+            ),
+            Grammar::Rule(name, _) => Diagram::NonTerminal(name.clone()),
+        }
+    }
+
+    fn size(&self) -> Size {
+        match self {
+            Diagram::Skip => Size{ w: 0, up: 0, down: 0 },
+            Diagram::Terminal(text) | Diagram::NonTerminal(text) => Size{
+                w: text.chars().count() as i64 * CHAR_WIDTH + 2 * GAP_H,
+                up: BOX_HEIGHT / 2,
+                down: BOX_HEIGHT / 2,
+            },
+            Diagram::Seq(items) => {
+                let sizes: Vec<Size> = items.iter().map(Diagram::size).collect();
+                Size{
+                    w: sizes.iter().map(|size| size.w).sum::<i64>() + GAP_H * (sizes.len().max(1) as i64 - 1),
+                    up: sizes.iter().map(|size| size.up).max().unwrap_or(0),
+                    down: sizes.iter().map(|size| size.down).max().unwrap_or(0),
+                }
+            },
+            Diagram::Choice(items) => {
+                let tracks = choice_tracks(items);
+                let last = items.last().map(Diagram::size).map_or(0, |size| size.down);
+                Size{
+                    w: items.iter().map(|item| item.size().w).max().unwrap_or(0) + 4 * ARC,
+                    up: items.first().map_or(0, |item| item.size().up),
+                    down: tracks.last().copied().unwrap_or(0) + last,
+                }
+            },
+            Diagram::Loop(body, repeat) => {
+                let body = body.size();
+                let repeat_size = repeat.size();
+                Size{
+                    w: body.w.max(repeat_size.w) + 4 * ARC,
+                    up: body.up,
+                    down: loop_track(&body, &repeat_size) + repeat_size.down,
+                }
+            },
+            Diagram::Group(child, _) => {
+                let child = child.size();
+                Size{
+                    w: child.w + 2 * GROUP_PAD,
+                    up: child.up + GROUP_PAD + GROUP_LABEL,
+                    down: child.down + GROUP_PAD,
+                }
+            },
+        }
+    }
+
+    fn render(&self, out: &mut String, x: i64, y: i64) {
+        match self {
+            Diagram::Skip => (),
+            Diagram::Terminal(text) | Diagram::NonTerminal(text) => {
+                let size = self.size();
+                let radius = if matches!(self, Diagram::Terminal(_)) { BOX_HEIGHT / 2 } else { 0 };
+                writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" class="{}"/>"#,
+                    x, y - size.up, size.w, BOX_HEIGHT, radius,
+                    if radius > 0 { "terminal" } else { "nonterminal" }).unwrap();
+                writeln!(out, r#"<text x="{}" y="{}">{}</text>"#,
+                    x + size.w / 2, y + 4, escape_xml(text)).unwrap();
+            },
+            Diagram::Seq(items) => {
+                let mut x = x;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        line(out, x, y, x + GAP_H, y);
+                        x += GAP_H;
+                    }
+                    item.render(out, x, y);
+                    x += item.size().w;
+                }
+            },
+            Diagram::Choice(items) => {
+                let w = self.size().w;
+                for (item, track) in items.iter().zip(choice_tracks(items)) {
+                    let item_w = item.size().w;
+                    let track_y = y + track;
+                    if track == 0 {
+                        line(out, x, y, x + 2 * ARC, y);
+                        line(out, x + w - 2 * ARC, y, x + w, y);
+                    } else {
+                        writeln!(out, r#"<path d="M{} {} q{} 0 {} {} v{} q0 {} {} {}"/>"#,
+                            x, y, ARC, ARC, ARC, track - 2 * ARC, ARC, ARC, ARC).unwrap();
+                        writeln!(out, r#"<path d="M{} {} q{} 0 {} {} v{} q0 {} {} {}"/>"#,
+                            x + w - 2 * ARC, track_y, ARC, ARC, -ARC, -(track - 2 * ARC), -ARC, ARC, -ARC).unwrap();
+                    }
+                    item.render(out, x + 2 * ARC, track_y);
+                    line(out, x + 2 * ARC + item_w, track_y, x + w - 2 * ARC, track_y);
+                }
+            },
+            Diagram::Loop(body, repeat) => {
+                let w = self.size().w;
+                let body_size = body.size();
+                let repeat_size = repeat.size();
+                let track = loop_track(&body_size, &repeat_size);
+                let track_y = y + track;
+
+                line(out, x, y, x + 2 * ARC, y);
+                body.render(out, x + 2 * ARC, y);
+                line(out, x + 2 * ARC + body_size.w, y, x + w, y);
+
+                writeln!(out, r#"<path d="M{} {} q{} 0 {} {} v{} q0 {} {} {}"/>"#,
+                    x + w - 2 * ARC, y, ARC, ARC, ARC, track - 2 * ARC, ARC, -ARC, ARC).unwrap();
+                let repeat_x = x + 2 * ARC + (w - 4 * ARC - repeat_size.w) / 2;
+                line(out, x + 2 * ARC, track_y, repeat_x, track_y);
+                repeat.render(out, repeat_x, track_y);
+                line(out, repeat_x + repeat_size.w, track_y, x + w - 2 * ARC, track_y);
+                writeln!(out, r#"<path d="M{} {} q{} 0 {} {} v{} q0 {} {} {}"/>"#,
+                    x + 2 * ARC, track_y, -ARC, -ARC, -ARC, -(track - 2 * ARC), -ARC, ARC, -ARC).unwrap();
+            },
+            Diagram::Group(child, label) => {
+                let size = self.size();
+                let child_size = child.size();
+                writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" class="group"/>"#,
+                    x, y - size.up, size.w, size.up + size.down).unwrap();
+                writeln!(out, r#"<text x="{}" y="{}" class="label">{}</text>"#,
+                    x + 4, y - size.up + GROUP_LABEL - 3, escape_xml(label)).unwrap();
+                line(out, x, y, x + GROUP_PAD, y);
+                child.render(out, x + GROUP_PAD, y);
+                line(out, x + GROUP_PAD + child_size.w, y, x + size.w, y);
+            },
+        }
+    }
+}
+
+// Offsets of the choice branches' tracks relative to the first one
+fn choice_tracks(items: &[Diagram]) -> Vec<i64> {
+    let mut tracks: Vec<i64> = Vec::new();
+    let mut previous_down = 0;
+    for item in items {
+        let size = item.size();
+        let track = match tracks.last() {
+            None => 0,
+            Some(last) => (last + previous_down + GAP_V + size.up).max(last + 2 * ARC),
+        };
+        tracks.push(track);
+        previous_down = size.down;
+    }
+    tracks
+}
+
+fn loop_track(body: &Size, repeat: &Size) -> i64 {
+    (body.down + GAP_V + repeat.up).max(2 * ARC)
+}
+
+fn line(out: &mut String, x1: i64, y1: i64, x2: i64, y2: i64) {
+    if x1 != x2 || y1 != y2 {
+        writeln!(out, r#"<path d="M{} {} L{} {}"/>"#, x1, y1, x2, y2).unwrap();
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const SVG_STYLE: &str = "
+path { fill: none; stroke: black; stroke-width: 1.5; }
+rect { fill: #eef; stroke: black; stroke-width: 1.5; }
+rect.group { fill: none; stroke: gray; stroke-dasharray: 4 3; }
+text { font: 13px monospace; text-anchor: middle; }
+text.label { font-size: 10px; text-anchor: start; fill: gray; }
+text.rule { font-size: 14px; font-weight: bold; text-anchor: start; }
+";
+
+// One railroad diagram per rule, stacked vertically in a single SVG document
+pub fn to_railroad_svg(parser: &dyn Parser) -> String {
+    const MARGIN: i64 = 20;
+    const TITLE: i64 = 24;
+
+    let mut body = String::new();
+    let mut width = 0;
+    let mut y = MARGIN;
+    for (name, grammar) in collect_rules(parser) {
+        let diagram = Diagram::from_grammar(&grammar);
+        let size = diagram.size();
+        writeln!(body, r#"<text x="{}" y="{}" class="rule">{}</text>"#, MARGIN, y + 14, escape_xml(&name)).unwrap();
+        let track_y = y + TITLE + size.up;
+        writeln!(body, r#"<path d="M{} {} v20 M{} {} v20"/>"#,
+            MARGIN, track_y - 10, MARGIN + 4, track_y - 10).unwrap();
+        line(&mut body, MARGIN + 4, track_y, MARGIN + 2 * ARC, track_y);
+        diagram.render(&mut body, MARGIN + 2 * ARC, track_y);
+        let end_x = MARGIN + 2 * ARC + size.w;
+        line(&mut body, end_x, track_y, end_x + 2 * ARC - 4, track_y);
+        writeln!(body, r#"<path d="M{} {} v20 M{} {} v20"/>"#,
+            end_x + 2 * ARC - 4, track_y - 10, end_x + 2 * ARC, track_y - 10).unwrap();
+        width = width.max(end_x + 2 * ARC + MARGIN);
+        y = track_y + size.down + MARGIN;
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n<style>{}</style>\n{}</svg>\n",
+        width, y, width, y, SVG_STYLE, body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+
+    fn make_path_parser() -> std::rc::Rc<dyn Parser> {
+        let number = make_named("number", make_number());
+        make_named("path", make_list(
+            make_seq(vec![make_capture(number.clone()), make_char(','), make_capture(number)]),
+            make_lit(" -> ")
+        ))
+    }
+
+    #[test]
+    fn test_to_ebnf() {
+        assert_eq!(
+            to_ebnf(&*make_path_parser()),
+            "path   ::= number \",\" number ( \" -> \" number \",\" number )*\n\
+             number ::= \"-\"? [0-9]+\n"
+        );
+
+        let words = make_seq(vec![make_lit("a\n\"b"), make_any(make_alt(make_word(), make_ws()))]);
+        assert_eq!(
+            to_ebnf(&*words),
+            "start    ::= \"a\" #xA '\"b' ( ALPHANUM+ | \" \" | #x9 | #xA )*\n\
+             ALPHANUM ::= /* any Unicode alphanumeric character */\n"
+        );
+    }
+
+    #[test]
+    fn test_to_railroad_svg() {
+        let svg = to_railroad_svg(&*make_path_parser());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(r#"class="rule""#).count(), 2);
+        assert!(svg.contains("&quot; -&gt; &quot;"));
+    }
+}
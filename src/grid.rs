@@ -1,21 +1,304 @@
 #![allow(dead_code)]
 
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+#[derive(Clone)]
 pub struct Grid<T: Copy> {
     data: Vec<T>,
     width: i64,
     height: i64,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Point {
-    pub x: i64,
-    pub y: i64,
+// Integer type usable as a point/vector coordinate. Grids store their size as
+// i64 and accept points with any coordinate type.
+pub trait Coord: 'static + Copy + Default + Debug + Eq + Ord + Hash
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+    fn as_i64(self) -> i64;
+    fn from_i64(value: i64) -> Self;
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Vector {
-    pub xd: i64,
-    pub yd: i64,
+macro_rules! impl_coord {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+
+                fn signum(self) -> Self {
+                    <$t>::signum(self)
+                }
+
+                fn as_i64(self) -> i64 {
+                    self as i64
+                }
+
+                fn from_i64(value: i64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_coord!(i8, i16, i32, i64, isize);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point<C = i64> {
+    pub x: C,
+    pub y: C,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Vector<C = i64> {
+    pub xd: C,
+    pub yd: C,
+}
+
+// Offsets of the orthogonal neighbors, and of all eight surrounding cells
+pub const NEIGHBORS4: [Vector; 4] = [
+    Vector{ xd: -1, yd: 0 }, Vector{ xd: 1, yd: 0 }, Vector{ xd: 0, yd: -1 }, Vector{ xd: 0, yd: 1 },
+];
+pub const NEIGHBORS8: [Vector; 8] = [
+    Vector{ xd: -1, yd: -1 }, Vector{ xd: 0, yd: -1 }, Vector{ xd: 1, yd: -1 },
+    Vector{ xd: -1, yd: 0 }, Vector{ xd: 1, yd: 0 },
+    Vector{ xd: -1, yd: 1 }, Vector{ xd: 0, yd: 1 }, Vector{ xd: 1, yd: 1 },
+];
+
+// Which way y grows in a grid as displayed: screen-space grids have row 0 at
+// the top, math-space grids (e.g. day17's tower) have y pointing up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YAxis {
+    Down,
+    Up,
+}
+
+// Orthogonal directions in clockwise order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction4 {
+    Up = 0,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction4 {
+    pub const ALL: [Direction4; 4] = [Direction4::Up, Direction4::Right, Direction4::Down, Direction4::Left];
+
+    pub const fn turn_right(self) -> Direction4 {
+        Direction4::ALL[(self as usize + 1) % 4]
+    }
+
+    pub const fn turn_left(self) -> Direction4 {
+        Direction4::ALL[(self as usize + 3) % 4]
+    }
+
+    pub const fn opposite(self) -> Direction4 {
+        Direction4::ALL[(self as usize + 2) % 4]
+    }
+
+    pub const fn as_vector(self, y_axis: YAxis) -> Vector {
+        let up = match y_axis {
+            YAxis::Down => -1,
+            YAxis::Up => 1,
+        };
+        match self {
+            Direction4::Up => Vector{ xd: 0, yd: up },
+            Direction4::Right => Vector{ xd: 1, yd: 0 },
+            Direction4::Down => Vector{ xd: 0, yd: -up },
+            Direction4::Left => Vector{ xd: -1, yd: 0 },
+        }
+    }
+
+    // The direction of a single orthogonal step
+    pub fn from_vector<C: Coord>(v: &Vector<C>, y_axis: YAxis) -> Option<Direction4> {
+        let v = Vector{ xd: v.xd.as_i64(), yd: v.yd.as_i64() };
+        Direction4::ALL.into_iter().find(|direction| direction.as_vector(y_axis) == v)
+    }
+
+    // Letters (U/R/D/L and compass N/E/S/W), ASCII arrows (^ > v <) and
+    // Unicode arrows
+    pub fn from_char(ch: char) -> Option<Direction4> {
+        match ch {
+            'U' | 'N' | '^' | '↑' => Some(Direction4::Up),
+            'R' | 'E' | '>' | '→' => Some(Direction4::Right),
+            'D' | 'S' | 'v' | '↓' => Some(Direction4::Down),
+            'L' | 'W' | '<' | '←' => Some(Direction4::Left),
+            _ => None,
+        }
+    }
+
+    pub fn arrow(self) -> char {
+        match self {
+            Direction4::Up => '^',
+            Direction4::Right => '>',
+            Direction4::Down => 'v',
+            Direction4::Left => '<',
+        }
+    }
+}
+
+impl std::str::FromStr for Direction4 {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Direction4::from_char(ch),
+            _ => None,
+        }.ok_or_else(|| format!("Expected a direction like U, R, D, L or an arrow, but got '{}'", text))
+    }
+}
+
+impl std::fmt::Display for Direction4 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.arrow())
+    }
+}
+
+// Orthogonal and diagonal directions in clockwise order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction8 {
+    Up = 0,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction8 {
+    pub const ALL: [Direction8; 8] = [
+        Direction8::Up, Direction8::UpRight, Direction8::Right, Direction8::DownRight,
+        Direction8::Down, Direction8::DownLeft, Direction8::Left, Direction8::UpLeft,
+    ];
+
+    // By 45 degrees
+    pub const fn turn_right(self) -> Direction8 {
+        Direction8::ALL[(self as usize + 1) % 8]
+    }
+
+    pub const fn turn_left(self) -> Direction8 {
+        Direction8::ALL[(self as usize + 7) % 8]
+    }
+
+    pub const fn opposite(self) -> Direction8 {
+        Direction8::ALL[(self as usize + 4) % 8]
+    }
+
+    pub const fn as_vector(self, y_axis: YAxis) -> Vector {
+        let up = match y_axis {
+            YAxis::Down => -1,
+            YAxis::Up => 1,
+        };
+        match self {
+            Direction8::Up => Vector{ xd: 0, yd: up },
+            Direction8::UpRight => Vector{ xd: 1, yd: up },
+            Direction8::Right => Vector{ xd: 1, yd: 0 },
+            Direction8::DownRight => Vector{ xd: 1, yd: -up },
+            Direction8::Down => Vector{ xd: 0, yd: -up },
+            Direction8::DownLeft => Vector{ xd: -1, yd: -up },
+            Direction8::Left => Vector{ xd: -1, yd: 0 },
+            Direction8::UpLeft => Vector{ xd: -1, yd: up },
+        }
+    }
+
+    pub fn from_char(ch: char) -> Option<Direction8> {
+        match ch {
+            '↗' => Some(Direction8::UpRight),
+            '↘' => Some(Direction8::DownRight),
+            '↙' => Some(Direction8::DownLeft),
+            '↖' => Some(Direction8::UpLeft),
+            ch => Direction4::from_char(ch).map(Direction8::from),
+        }
+    }
+}
+
+impl From<Direction4> for Direction8 {
+    fn from(direction: Direction4) -> Self {
+        Direction8::ALL[direction as usize * 2]
+    }
+}
+
+impl std::str::FromStr for Direction8 {
+    type Err = String;
+
+    // A single letter or arrow as for `Direction4`, or two letters for the
+    // diagonals (UR, NE, ...)
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut chars = text.chars();
+        let parsed = match (chars.next(), chars.next(), chars.next()) {
+            (Some(ch), None, None) => Direction8::from_char(ch),
+            (Some(first), Some(second), None) => {
+                match (Direction4::from_char(first), Direction4::from_char(second)) {
+                    (Some(Direction4::Up), Some(Direction4::Right)) => Some(Direction8::UpRight),
+                    (Some(Direction4::Down), Some(Direction4::Right)) => Some(Direction8::DownRight),
+                    (Some(Direction4::Down), Some(Direction4::Left)) => Some(Direction8::DownLeft),
+                    (Some(Direction4::Up), Some(Direction4::Left)) => Some(Direction8::UpLeft),
+                    _ => None,
+                }
+            },
+            _ => None,
+        };
+        parsed.ok_or_else(|| format!("Expected a direction like U, UR, NE or an arrow, but got '{}'", text))
+    }
+}
+
+// Which cells count as adjacent for region algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    pub fn offsets(&self) -> &'static [Vector] {
+        match self {
+            Connectivity::Four => &NEIGHBORS4,
+            Connectivity::Eight => &NEIGHBORS8,
+        }
+    }
+}
+
+// Breadth-first walk from `sources`. `enter(node, steps)` is called for each
+// source and for each successor of an entered node, and tells whether the node
+// is entered, which it should only be once. Nodes are entered in breadth-first
+// order, so callers record what they need there.
+pub fn breadth_first<N, Sources, SuccessorsFn, Successors, EnterFn>(sources: Sources, mut successors: SuccessorsFn,
+    mut enter: EnterFn)
+where
+    Sources: IntoIterator<Item = N>,
+    SuccessorsFn: FnMut(&N) -> Successors,
+    Successors: IntoIterator<Item = N>,
+    EnterFn: FnMut(&N, usize) -> bool,
+{
+    let mut queue = VecDeque::new();
+    for source in sources {
+        if enter(&source, 0) {
+            queue.push_back((source, 0));
+        }
+    }
+    while let Some((node, steps)) = queue.pop_front() {
+        for next in successors(&node) {
+            if enter(&next, steps + 1) {
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -24,8 +307,8 @@ pub struct Dimensions {
     pub h: i64,
 }
 
-impl Point {
-    pub fn as_vector(&self) -> Vector {
+impl <C: Coord> Point<C> {
+    pub fn as_vector(&self) -> Vector<C> {
         Vector{
             xd: self.x,
             yd: self.y
@@ -33,32 +316,33 @@ impl Point {
     }
 }
 
-impl Vector {
-    pub fn l1_norm(&self) -> i64 {
+impl <C: Coord> Vector<C> {
+    pub fn l1_norm(&self) -> C {
         self.xd.abs() + self.yd.abs()
     }
 
-    pub fn signum(&self) -> Vector {
-        fn aux(x: i64) -> i64 {
-            if x < 0 {
-                -1
-            } else if x == 0 {
-                0
-            } else {
-                1
-            }
-        }
+    // Chebyshev distance, the number of king moves
+    pub fn linf_norm(&self) -> C {
+        std::cmp::max(self.xd.abs(), self.yd.abs())
+    }
+
+    pub fn l2_norm(&self) -> f64 {
+        let (xd, yd) = (self.xd.as_i64() as f64, self.yd.as_i64() as f64);
+        (xd * xd + yd * yd).sqrt()
+    }
+
+    pub fn signum(&self) -> Vector<C> {
         Vector{
-            xd: aux(self.xd),
-            yd: aux(self.yd),
+            xd: self.xd.signum(),
+            yd: self.yd.signum(),
         }
     }
 }
 
-impl std::ops::Add<&Vector> for &Point {
-    type Output = Point;
+impl <C: Coord> Add<&Vector<C>> for &Point<C> {
+    type Output = Point<C>;
 
-    fn add(self, rhs: &Vector) -> Self::Output {
+    fn add(self, rhs: &Vector<C>) -> Self::Output {
         Point{
             x: self.x + rhs.xd,
             y: self.y + rhs.yd,
@@ -66,10 +350,18 @@ impl std::ops::Add<&Vector> for &Point {
     }
 }
 
-impl std::ops::Sub<&Vector> for &Point {
-    type Output = Point;
+impl <C: Coord> Add<Vector<C>> for Point<C> {
+    type Output = Point<C>;
+
+    fn add(self, rhs: Vector<C>) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl <C: Coord> Sub<&Vector<C>> for &Point<C> {
+    type Output = Point<C>;
 
-    fn sub(self, rhs: &Vector) -> Self::Output {
+    fn sub(self, rhs: &Vector<C>) -> Self::Output {
         Point{
             x: self.x - rhs.xd,
             y: self.y - rhs.yd,
@@ -77,10 +369,18 @@ impl std::ops::Sub<&Vector> for &Point {
     }
 }
 
-impl std::ops::Sub<&Point> for &Point {
-    type Output = Vector;
+impl <C: Coord> Sub<Vector<C>> for Point<C> {
+    type Output = Point<C>;
+
+    fn sub(self, rhs: Vector<C>) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl <C: Coord> Sub<&Point<C>> for &Point<C> {
+    type Output = Vector<C>;
 
-    fn sub(self, rhs: &Point) -> Self::Output {
+    fn sub(self, rhs: &Point<C>) -> Self::Output {
         Vector{
             xd: self.x - rhs.x,
             yd: self.y - rhs.y,
@@ -88,6 +388,138 @@ impl std::ops::Sub<&Point> for &Point {
     }
 }
 
+impl <C: Coord> Sub<Point<C>> for Point<C> {
+    type Output = Vector<C>;
+
+    fn sub(self, rhs: Point<C>) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl <C: Coord> Add<&Vector<C>> for &Vector<C> {
+    type Output = Vector<C>;
+
+    fn add(self, rhs: &Vector<C>) -> Self::Output {
+        Vector{
+            xd: self.xd + rhs.xd,
+            yd: self.yd + rhs.yd,
+        }
+    }
+}
+
+impl <C: Coord> Add<Vector<C>> for Vector<C> {
+    type Output = Vector<C>;
+
+    fn add(self, rhs: Vector<C>) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl <C: Coord> Sub<&Vector<C>> for &Vector<C> {
+    type Output = Vector<C>;
+
+    fn sub(self, rhs: &Vector<C>) -> Self::Output {
+        Vector{
+            xd: self.xd - rhs.xd,
+            yd: self.yd - rhs.yd,
+        }
+    }
+}
+
+impl <C: Coord> Sub<Vector<C>> for Vector<C> {
+    type Output = Vector<C>;
+
+    fn sub(self, rhs: Vector<C>) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl <C: Coord> Mul<C> for &Vector<C> {
+    type Output = Vector<C>;
+
+    fn mul(self, rhs: C) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl <C: Coord> Mul<C> for Vector<C> {
+    type Output = Vector<C>;
+
+    fn mul(self, rhs: C) -> Self::Output {
+        Vector{
+            xd: self.xd * rhs,
+            yd: self.yd * rhs,
+        }
+    }
+}
+
+impl <C: Coord> Neg for &Vector<C> {
+    type Output = Vector<C>;
+
+    fn neg(self) -> Self::Output {
+        Vector{
+            xd: -self.xd,
+            yd: -self.yd,
+        }
+    }
+}
+
+impl <C: Coord> Neg for Vector<C> {
+    type Output = Vector<C>;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl <C: Coord> AddAssign<Vector<C>> for Point<C> {
+    fn add_assign(&mut self, rhs: Vector<C>) {
+        *self = *self + rhs;
+    }
+}
+
+impl <C: Coord> SubAssign<Vector<C>> for Point<C> {
+    fn sub_assign(&mut self, rhs: Vector<C>) {
+        *self = *self - rhs;
+    }
+}
+
+impl <C: Coord> AddAssign<Vector<C>> for Vector<C> {
+    fn add_assign(&mut self, rhs: Vector<C>) {
+        *self = *self + rhs;
+    }
+}
+
+impl <C: Coord> SubAssign<Vector<C>> for Vector<C> {
+    fn sub_assign(&mut self, rhs: Vector<C>) {
+        *self = *self - rhs;
+    }
+}
+
+impl <C: Coord + std::fmt::Display> std::fmt::Display for Point<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl <C: Coord + std::fmt::Display> std::fmt::Display for Vector<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}, {}>", self.xd, self.yd)
+    }
+}
+
+impl <C: Coord> From<(C, C)> for Point<C> {
+    fn from((x, y): (C, C)) -> Self {
+        Point{ x, y }
+    }
+}
+
+impl <C: Coord> From<(C, C)> for Vector<C> {
+    fn from((xd, yd): (C, C)) -> Self {
+        Vector{ xd, yd }
+    }
+}
+
 impl <T: Copy> Grid<T> {
 
     pub fn new(width: i64, height: i64, value: T) -> Grid<T> {
@@ -98,60 +530,1505 @@ impl <T: Copy> Grid<T> {
         }
     }
 
-    pub fn get(&self, p: &Point) -> T {
-        assert!(p.x >= 0 && p.x < self.width);
-        assert!(p.y >= 0 && p.y < self.height);
-        self.data[(p.x + p.y * self.width) as usize]
+    pub fn width(&self) -> i64 {
+        self.width
     }
 
-    pub fn get_or_default(&self, p: &Point, default: T) -> T {
-        if p.x < 0 || p.x >= self.width || p.y < 0 || p.y >= self.height {
-            default
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        Dimensions{ w: self.width, h: self.height }
+    }
+
+    fn index<C: Coord>(&self, p: &Point<C>) -> Option<usize> {
+        let (x, y) = (p.x.as_i64(), p.y.as_i64());
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            None
         } else {
-            self.data[(p.x + p.y * self.width) as usize]
+            Some((x + y * self.width) as usize)
+        }
+    }
+
+    pub fn get<C: Coord>(&self, p: &Point<C>) -> T {
+        match self.index(p) {
+            Some(index) => self.data[index],
+            None => panic!("Point {:?} out of bounds for {}x{} grid", p, self.width, self.height),
+        }
+    }
+
+    pub fn get_or_default<C: Coord>(&self, p: &Point<C>, default: T) -> T {
+        match self.index(p) {
+            Some(index) => self.data[index],
+            None => default,
+        }
+    }
+
+    pub fn set<C: Coord>(&mut self, p: &Point<C>, value: T) {
+        match self.index(p) {
+            Some(index) => self.data[index] = value,
+            None => panic!("Point {:?} out of bounds for {}x{} grid", p, self.width, self.height),
+        }
+    }
+
+    pub fn is_in_bounds<C: Coord>(&self, p: &Point<C>) -> bool {
+        self.index(p).is_some()
+    }
+
+    // Cells of row `y` from left to right, `.rev()` walks right to left
+    pub fn row(&self, y: i64) -> impl DoubleEndedIterator<Item = (Point, T)> + '_ {
+        assert!(y >= 0 && y < self.height);
+        (0..self.width).map(move |x| {
+            let p = Point{ x, y };
+            (p, self.get(&p))
+        })
+    }
+
+    // Cells of column `x` from top to bottom, `.rev()` walks bottom to top
+    pub fn col(&self, x: i64) -> impl DoubleEndedIterator<Item = (Point, T)> + '_ {
+        assert!(x >= 0 && x < self.width);
+        (0..self.height).map(move |y| {
+            let p = Point{ x, y };
+            (p, self.get(&p))
+        })
+    }
+
+    // All points in row-major order
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point{ x, y }))
+    }
+
+    // `p` shifted by each of the offsets, skipping points outside the grid
+    pub fn stencil<'a, C: Coord>(&'a self, p: &Point<C>, offsets: &'a [Vector]) -> impl Iterator<Item = Point<C>> + 'a {
+        let p = *p;
+        offsets.iter()
+            .map(move |offset| Point{
+                x: p.x + C::from_i64(offset.xd),
+                y: p.y + C::from_i64(offset.yd),
+            })
+            .filter(move |neighbor| self.is_in_bounds(neighbor))
+    }
+
+    pub fn neighbors4<C: Coord>(&self, p: &Point<C>) -> impl Iterator<Item = Point<C>> + '_ {
+        self.stencil(p, &NEIGHBORS4)
+    }
+
+    pub fn neighbors8<C: Coord>(&self, p: &Point<C>) -> impl Iterator<Item = Point<C>> + '_ {
+        self.stencil(p, &NEIGHBORS8)
+    }
+
+    // Breadth-first walk from `start` over neighbors satisfying `predicate`,
+    // marking each reached cell in `seen` and passing it to `visit`
+    fn fill_unseen<PredicateFn, VisitFn>(&self, start: &Point, offsets: &[Vector], seen: &mut Grid<bool>,
+        predicate: &PredicateFn, mut visit: VisitFn)
+    where
+        PredicateFn: Fn(T) -> bool,
+        VisitFn: FnMut(Point)
+    {
+        breadth_first([*start], |p: &Point| self.stencil(p, offsets), |p, _| {
+            if seen.get(p) || !predicate(self.get(p)) {
+                return false;
+            }
+            seen.set(p, true);
+            visit(*p);
+            true
+        });
+    }
+
+    // Points reachable from `start` through neighbors whose value satisfies
+    // `predicate`, in breadth-first order. Empty if `start` itself does not
+    // satisfy it.
+    pub fn flood_fill<PredicateFn: Fn(T) -> bool>(&self, start: &Point, connectivity: Connectivity,
+        predicate: PredicateFn) -> Vec<Point>
+    {
+        let mut filled = Vec::new();
+        let mut seen = Grid::new(self.width, self.height, false);
+        self.fill_unseen(start, connectivity.offsets(), &mut seen, &predicate, |p| filled.push(p));
+        filled
+    }
+
+    // Numbers the connected regions of cells satisfying `predicate` in
+    // row-major order of their first cell. Returns the label of each cell
+    // (None for cells not satisfying it) and the size of each region.
+    pub fn label_components<PredicateFn: Fn(T) -> bool>(&self, connectivity: Connectivity,
+        predicate: PredicateFn) -> (Grid<Option<usize>>, Vec<usize>)
+    {
+        let mut labels = Grid::new(self.width, self.height, None);
+        let mut sizes = Vec::new();
+        let mut seen = Grid::new(self.width, self.height, false);
+        for p in self.points() {
+            if seen.get(&p) || !predicate(self.get(&p)) {
+                continue;
+            }
+            let label = sizes.len();
+            let mut size = 0;
+            self.fill_unseen(&p, connectivity.offsets(), &mut seen, &predicate, |q| {
+                labels.set(&q, Some(label));
+                size += 1;
+            });
+            sizes.push(size);
+        }
+        (labels, sizes)
+    }
+
+    // Cells from `from` (exclusive) in steps of `step` until leaving the grid
+    pub fn ray<C: Coord>(&self, from: &Point<C>, step: Vector<C>) -> impl Iterator<Item = (Point<C>, T)> + '_ {
+        assert!(step != Vector{ xd: C::ZERO, yd: C::ZERO });
+        let mut current = *from;
+        std::iter::from_fn(move || {
+            current += step;
+            self.index(&current).map(|index| (current, self.data[index]))
+        })
+    }
+}
+
+impl <T: Copy> Grid<T> {
+    pub fn as_view(&self) -> GridView<'_, T> {
+        GridView{
+            grid: self,
+            origin: Point{ x: 0, y: 0 },
+            x_step: Vector{ xd: 1, yd: 0 },
+            y_step: Vector{ xd: 0, yd: 1 },
+            width: self.width,
+            height: self.height,
         }
     }
 
-    pub fn set(&mut self, p: &Point, value: T) {
-        assert!(p.x >= 0 && p.x < self.width);
-        assert!(p.y >= 0 && p.y < self.height);
-        self.data[(p.x + p.y * self.width) as usize] = value
+    // Rectangle from `from` towards `from + size` (exclusive), with `from` as its
+    // (0, 0)
+    pub fn view(&self, from: &Point, size: &Vector) -> GridView<'_, T> {
+        self.as_view().view(from, size)
+    }
+
+    pub fn transpose(&self) -> Grid<T> {
+        self.as_view().transpose().to_grid()
+    }
+
+    // Clockwise, as displayed with y pointing down
+    pub fn rotate90(&self) -> Grid<T> {
+        self.as_view().rotate90().to_grid()
+    }
+
+    // Mirrors left and right
+    pub fn flip_x(&self) -> Grid<T> {
+        self.as_view().flip_x().to_grid()
+    }
+
+    // Mirrors top and bottom
+    pub fn flip_y(&self) -> Grid<T> {
+        self.as_view().flip_y().to_grid()
+    }
+}
+
+// Borrowed rectangle of a grid, possibly transposed, rotated or mirrored, with
+// its own coordinates: view point (x, y) is grid point
+// `origin + x * x_step + y * y_step`.
+#[derive(Clone, Copy)]
+pub struct GridView<'a, T: Copy> {
+    grid: &'a Grid<T>,
+    origin: Point,
+    x_step: Vector,
+    y_step: Vector,
+    width: i64,
+    height: i64,
+}
+
+impl <'a, T: Copy> GridView<'a, T> {
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        Dimensions{ w: self.width, h: self.height }
+    }
+
+    // The point of the underlying grid that view point `p` shows
+    pub fn grid_point(&self, p: &Point) -> Point {
+        Point{
+            x: self.origin.x + p.x * self.x_step.xd + p.y * self.y_step.xd,
+            y: self.origin.y + p.x * self.x_step.yd + p.y * self.y_step.yd,
+        }
     }
 
     pub fn is_in_bounds(&self, p: &Point) -> bool {
         p.x >= 0 && p.x < self.width && p.y >= 0 && p.y < self.height
     }
-}
 
-impl <T: Copy + std::fmt::Display> Grid<T> {
-    fn dump_helper<GetterFn: Fn(&Point) -> T>(&self, from: &Point, size: &Vector, getter: GetterFn) {
-        assert!(size.xd.abs() <= 30 && size.yd.abs() <= 30);
-        let to = from + size;
-        let mut row = from.y;
-        let step = size.signum();
-        while row != to.y {
-            let mut col = from.x;
-            while col != to.x {
-                print!("{}", getter(&Point{ x: col, y: row }));
-                col += step.xd;
-            }
-            println!("");
-            row += step.yd;
+    pub fn get(&self, p: &Point) -> T {
+        if !self.is_in_bounds(p) {
+            panic!("Point {:?} out of bounds for {}x{} view", p, self.width, self.height);
         }
+        self.grid.get(&self.grid_point(p))
     }
 
-    pub fn dump(&self) {
-        self.dump_helper(&Point{ x: 0, y: 0 }, &Vector{ xd: self.width, yd: self.height }, |point| self.get(point));
+    pub fn get_or_default(&self, p: &Point, default: T) -> T {
+        if self.is_in_bounds(p) { self.grid.get(&self.grid_point(p)) } else { default }
     }
 
-    pub fn dump_part(&self, from: &Point, size: &Vector) {
-        assert!(self.is_in_bounds(from));
-        assert!(self.is_in_bounds(&(&(from + size) - &size.signum())));
+    // Cells of row `y` from left to right, with view coordinates
+    pub fn row(&self, y: i64) -> impl DoubleEndedIterator<Item = (Point, T)> + 'a {
+        assert!(y >= 0 && y < self.height);
+        let view = *self;
+        (0..self.width).map(move |x| {
+            let p = Point{ x, y };
+            (p, view.get(&p))
+        })
+    }
 
-        self.dump_helper(from, size, |point| self.get(point));
+    // Cells of column `x` from top to bottom, with view coordinates
+    pub fn col(&self, x: i64) -> impl DoubleEndedIterator<Item = (Point, T)> + 'a {
+        assert!(x >= 0 && x < self.width);
+        let view = *self;
+        (0..self.height).map(move |y| {
+            let p = Point{ x, y };
+            (p, view.get(&p))
+        })
     }
 
-    pub fn dump_part_default(&self, from: &Point, size: &Vector, default: T) {
-        self.dump_helper(from, size, |point| self.get_or_default(point, default));
+    // All view points in row-major order
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point{ x, y }))
+    }
+
+    // Rectangle of this view from `from` towards `from + size` (exclusive)
+    pub fn view(&self, from: &Point, size: &Vector) -> GridView<'a, T> {
+        assert!(size.xd >= 0 && size.yd >= 0, "Negative view size {:?}", size);
+        assert!(from.x >= 0 && from.y >= 0 && from.x + size.xd <= self.width && from.y + size.yd <= self.height,
+            "View at {:?} of size {:?} exceeds {}x{}", from, size, self.width, self.height);
+        GridView{
+            origin: self.grid_point(from),
+            width: size.xd,
+            height: size.yd,
+            ..*self
+        }
+    }
+
+    pub fn transpose(&self) -> GridView<'a, T> {
+        GridView{
+            x_step: self.y_step,
+            y_step: self.x_step,
+            width: self.height,
+            height: self.width,
+            ..*self
+        }
+    }
+
+    // Clockwise, as displayed with y pointing down: the bottom left corner
+    // becomes the top left one
+    pub fn rotate90(&self) -> GridView<'a, T> {
+        GridView{
+            origin: self.grid_point(&Point{ x: 0, y: self.height - 1 }),
+            x_step: Vector{ xd: -self.y_step.xd, yd: -self.y_step.yd },
+            y_step: self.x_step,
+            width: self.height,
+            height: self.width,
+            ..*self
+        }
+    }
+
+    pub fn flip_x(&self) -> GridView<'a, T> {
+        GridView{
+            origin: self.grid_point(&Point{ x: self.width - 1, y: 0 }),
+            x_step: Vector{ xd: -self.x_step.xd, yd: -self.x_step.yd },
+            ..*self
+        }
+    }
+
+    pub fn flip_y(&self) -> GridView<'a, T> {
+        GridView{
+            origin: self.grid_point(&Point{ x: 0, y: self.height - 1 }),
+            y_step: Vector{ xd: -self.y_step.xd, yd: -self.y_step.yd },
+            ..*self
+        }
     }
-}
\ No newline at end of file
+
+    pub fn to_grid(self) -> Grid<T> {
+        Grid{
+            data: self.points().map(|p| self.get(&p)).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+// Boolean grid of at most 64 columns storing each row as a bitmask, bit x of
+// row y being cell (x, y). Rows start at y = 0 and are added on demand, so the
+// height is one more than the highest row ever written. Shapes given as row
+// masks can be tested and placed a word per row at a time.
+#[derive(Clone)]
+pub struct BitGrid {
+    rows: Vec<u64>,
+    width: i64,
+}
+
+impl BitGrid {
+    pub fn new(width: i64) -> BitGrid {
+        assert!(width > 0 && width <= 64, "BitGrid width {} is not in 1..=64", width);
+        BitGrid{ rows: Vec::new(), width }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.rows.len() as i64
+    }
+
+    // Rows beyond the height read as empty
+    pub fn row_mask(&self, y: i64) -> u64 {
+        assert!(y >= 0, "Row {} out of bounds", y);
+        self.rows.get(y as usize).copied().unwrap_or(0)
+    }
+
+    fn ensure_rows(&mut self, height: i64) {
+        if height > self.height() {
+            self.rows.resize(height as usize, 0);
+        }
+    }
+
+    pub fn get<C: Coord>(&self, p: &Point<C>) -> bool {
+        let (x, y) = (p.x.as_i64(), p.y.as_i64());
+        assert!(x >= 0 && x < self.width && y >= 0, "Point {:?} out of bounds for {} wide bit grid", p, self.width);
+        self.row_mask(y) & (1 << x) != 0
+    }
+
+    pub fn set<C: Coord>(&mut self, p: &Point<C>, value: bool) {
+        let (x, y) = (p.x.as_i64(), p.y.as_i64());
+        assert!(x >= 0 && x < self.width && y >= 0, "Point {:?} out of bounds for {} wide bit grid", p, self.width);
+        self.ensure_rows(y + 1);
+        if value {
+            self.rows[y as usize] |= 1 << x;
+        } else {
+            self.rows[y as usize] &= !(1 << x);
+        }
+    }
+
+    // `shape` row i shifted right by `at.x` and placed on row `at.y + i`. Returns
+    // None if any of its cells would end up outside the columns or below row 0.
+    fn shifted_rows<'a>(&self, shape: &'a [u64], at: &Point) -> Option<impl Iterator<Item = (usize, u64)> + 'a> {
+        if at.y < 0 || at.x < 0 || at.x >= self.width {
+            return None;
+        }
+        let columns = if self.width == 64 { u64::MAX } else { (1 << self.width) - 1 };
+        let shift = at.x as u32;
+        let fits = shape.iter().all(|row| row.leading_zeros() >= shift && (row << shift) & !columns == 0);
+        let first_row = at.y as usize;
+        fits.then(|| shape.iter().enumerate().map(move |(i, row)| (first_row + i, row << shift)))
+    }
+
+    // Whether `shape` placed at `at` overlaps a set cell, the space left and
+    // right of the columns and below row 0 counting as set
+    pub fn collides(&self, shape: &[u64], at: &Point) -> bool {
+        match self.shifted_rows(shape, at) {
+            None => true,
+            Some(mut rows) => rows.any(|(y, row)| self.rows.get(y).is_some_and(|mask| mask & row != 0)),
+        }
+    }
+
+    // Sets the cells of `shape` placed at `at`
+    pub fn place(&mut self, shape: &[u64], at: &Point) {
+        let rows: Vec<(usize, u64)> = self.shifted_rows(shape, at)
+            .unwrap_or_else(|| panic!("Shape at {:?} exceeds {} wide bit grid", at, self.width))
+            .collect();
+        for (y, row) in rows {
+            self.ensure_rows(y as i64 + 1);
+            self.rows[y] |= row;
+        }
+    }
+
+    pub fn to_grid(&self) -> Grid<bool> {
+        let mut grid = Grid::new(self.width, self.height(), false);
+        for p in grid.points() {
+            grid.set(&p, self.get(&p));
+        }
+        grid
+    }
+}
+
+const CHUNK_BITS: i64 = 5;
+const CHUNK_SIZE: i64 = 1 << CHUNK_BITS;
+
+// Unbounded grid with the same accessors as `Grid`. Cells are allocated in
+// CHUNK_SIZE x CHUNK_SIZE chunks on first write, unwritten cells read as the
+// default value. Coordinates may be negative.
+pub struct InfiniteGrid<T: Copy> {
+    chunks: HashMap<Point, Vec<T>>,
+    default: T,
+    bounds: Option<(Point, Point)>,
+}
+
+impl <T: Copy> InfiniteGrid<T> {
+    pub fn new(default: T) -> InfiniteGrid<T> {
+        InfiniteGrid{
+            chunks: HashMap::new(),
+            default,
+            bounds: None,
+        }
+    }
+
+    fn locate<C: Coord>(p: &Point<C>) -> (Point, usize) {
+        let (x, y) = (p.x.as_i64(), p.y.as_i64());
+        let chunk = Point{ x: x >> CHUNK_BITS, y: y >> CHUNK_BITS };
+        let offset = (x & (CHUNK_SIZE - 1)) + (y & (CHUNK_SIZE - 1)) * CHUNK_SIZE;
+        (chunk, offset as usize)
+    }
+
+    pub fn get<C: Coord>(&self, p: &Point<C>) -> T {
+        let (chunk, offset) = InfiniteGrid::<T>::locate(p);
+        match self.chunks.get(&chunk) {
+            Some(cells) => cells[offset],
+            None => self.default,
+        }
+    }
+
+    // Reads like `Grid::get_or_default` on the dense grid `to_grid` gives: in
+    // the bounding box of written cells, unwritten ones read as the grid's own
+    // default, only points outside of it read as `default`. The box grows with
+    // every write, so the same point may change from `default` to the grid's
+    // default without being written.
+    pub fn get_or_default<C: Coord>(&self, p: &Point<C>, default: T) -> T {
+        if self.is_in_bounds(p) {
+            self.get(p)
+        } else {
+            default
+        }
+    }
+
+    pub fn set<C: Coord>(&mut self, p: &Point<C>, value: T) {
+        let (chunk, offset) = InfiniteGrid::<T>::locate(p);
+        let default = self.default;
+        self.chunks.entry(chunk)
+            .or_insert_with(|| vec![default; (CHUNK_SIZE * CHUNK_SIZE) as usize])[offset] = value;
+
+        let p = Point{ x: p.x.as_i64(), y: p.y.as_i64() };
+        self.bounds = match self.bounds {
+            None => Some((p, p)),
+            Some((min, max)) => Some((
+                Point{ x: min.x.min(p.x), y: min.y.min(p.y) },
+                Point{ x: max.x.max(p.x), y: max.y.max(p.y) },
+            )),
+        };
+    }
+
+    pub fn is_in_bounds<C: Coord>(&self, p: &Point<C>) -> bool {
+        match self.bounds {
+            None => false,
+            Some((min, max)) => {
+                let (x, y) = (p.x.as_i64(), p.y.as_i64());
+                x >= min.x && x <= max.x && y >= min.y && y <= max.y
+            }
+        }
+    }
+
+    // Smallest and largest corner (both inclusive) of all cells written so far
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        self.bounds
+    }
+
+    // Copies the bounding box into a dense grid, returning it together with the
+    // point its (0, 0) corresponds to
+    pub fn to_grid(&self) -> Option<(Grid<T>, Point)> {
+        let (min, max) = self.bounds?;
+        let mut grid = Grid::new(max.x - min.x + 1, max.y - min.y + 1, self.default);
+        for p in grid.points() {
+            grid.set(&p, self.get(&(p + min.as_vector())));
+        }
+        Some((grid, min))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3<C = i64> {
+    pub x: C,
+    pub y: C,
+    pub z: C,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector3<C = i64> {
+    pub xd: C,
+    pub yd: C,
+    pub zd: C,
+}
+
+// Offsets of the six cells sharing a face
+pub const NEIGHBORS6: [Vector3; 6] = [
+    Vector3{ xd: -1, yd: 0, zd: 0 }, Vector3{ xd: 1, yd: 0, zd: 0 },
+    Vector3{ xd: 0, yd: -1, zd: 0 }, Vector3{ xd: 0, yd: 1, zd: 0 },
+    Vector3{ xd: 0, yd: 0, zd: -1 }, Vector3{ xd: 0, yd: 0, zd: 1 },
+];
+
+// Offsets within the surrounding 3x3x3 cube with at most `max_norm` non-zero
+// coordinates, i.e. sharing a face (1), an edge (2) or a corner (3)
+const fn cube_neighbors<const N: usize>(max_norm: i64) -> [Vector3; N] {
+    let mut offsets = [Vector3{ xd: 0, yd: 0, zd: 0 }; N];
+    let (mut count, mut i) = (0, 0);
+    while i < 27 {
+        let offset = Vector3{ xd: i % 3 - 1, yd: i / 3 % 3 - 1, zd: i / 9 - 1 };
+        let norm = (offset.xd != 0) as i64 + (offset.yd != 0) as i64 + (offset.zd != 0) as i64;
+        if norm > 0 && norm <= max_norm {
+            offsets[count] = offset;
+            count += 1;
+        }
+        i += 1;
+    }
+    assert!(count == N);
+    offsets
+}
+
+// Offsets of the cells sharing a face or an edge
+pub const NEIGHBORS18: [Vector3; 18] = cube_neighbors(2);
+// Offsets of all cells touching, corners included
+pub const NEIGHBORS26: [Vector3; 26] = cube_neighbors(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity3 {
+    Six,
+    Eighteen,
+    TwentySix,
+}
+
+impl Connectivity3 {
+    pub fn offsets(&self) -> &'static [Vector3] {
+        match self {
+            Connectivity3::Six => &NEIGHBORS6,
+            Connectivity3::Eighteen => &NEIGHBORS18,
+            Connectivity3::TwentySix => &NEIGHBORS26,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Dimensions3 {
+    pub w: i64,
+    pub h: i64,
+    pub d: i64,
+}
+
+impl <C: Coord> Point3<C> {
+    pub fn as_vector(&self) -> Vector3<C> {
+        Vector3{
+            xd: self.x,
+            yd: self.y,
+            zd: self.z,
+        }
+    }
+}
+
+impl <C: Coord> Vector3<C> {
+    pub fn l1_norm(&self) -> C {
+        self.xd.abs() + self.yd.abs() + self.zd.abs()
+    }
+
+    pub fn signum(&self) -> Vector3<C> {
+        Vector3{
+            xd: self.xd.signum(),
+            yd: self.yd.signum(),
+            zd: self.zd.signum(),
+        }
+    }
+}
+
+impl <C: Coord> Add<&Vector3<C>> for &Point3<C> {
+    type Output = Point3<C>;
+
+    fn add(self, rhs: &Vector3<C>) -> Self::Output {
+        Point3{
+            x: self.x + rhs.xd,
+            y: self.y + rhs.yd,
+            z: self.z + rhs.zd,
+        }
+    }
+}
+
+impl <C: Coord> Add<Vector3<C>> for Point3<C> {
+    type Output = Point3<C>;
+
+    fn add(self, rhs: Vector3<C>) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl <C: Coord> Sub<&Vector3<C>> for &Point3<C> {
+    type Output = Point3<C>;
+
+    fn sub(self, rhs: &Vector3<C>) -> Self::Output {
+        Point3{
+            x: self.x - rhs.xd,
+            y: self.y - rhs.yd,
+            z: self.z - rhs.zd,
+        }
+    }
+}
+
+impl <C: Coord> Sub<Vector3<C>> for Point3<C> {
+    type Output = Point3<C>;
+
+    fn sub(self, rhs: Vector3<C>) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+impl <C: Coord> Sub<&Point3<C>> for &Point3<C> {
+    type Output = Vector3<C>;
+
+    fn sub(self, rhs: &Point3<C>) -> Self::Output {
+        Vector3{
+            xd: self.x - rhs.x,
+            yd: self.y - rhs.y,
+            zd: self.z - rhs.z,
+        }
+    }
+}
+
+impl <C: Coord> Sub<Point3<C>> for Point3<C> {
+    type Output = Vector3<C>;
+
+    fn sub(self, rhs: Point3<C>) -> Self::Output {
+        &self - &rhs
+    }
+}
+
+// Smallest and largest corner (both inclusive) of a set of points
+pub fn bounding_box3<'a, C: Coord, I>(points: I) -> Option<(Point3<C>, Point3<C>)>
+where
+    I: IntoIterator<Item = &'a Point3<C>>
+{
+    points.into_iter().fold(None, |bounds, p| match bounds {
+        None => Some((*p, *p)),
+        Some((min, max)) => Some((
+            Point3{ x: min.x.min(p.x), y: min.y.min(p.y), z: min.z.min(p.z) },
+            Point3{ x: max.x.max(p.x), y: max.y.max(p.y), z: max.z.max(p.z) },
+        )),
+    })
+}
+
+// Dense 3D counterpart of `Grid`, cells are stored x first, then y, then z
+#[derive(Clone)]
+pub struct Grid3<T: Copy> {
+    data: Vec<T>,
+    width: i64,
+    height: i64,
+    depth: i64,
+}
+
+impl <T: Copy> Grid3<T> {
+    pub fn new(width: i64, height: i64, depth: i64, value: T) -> Grid3<T> {
+        Grid3{
+            data: vec![value; (width * height * depth) as usize],
+            width,
+            height,
+            depth,
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn depth(&self) -> i64 {
+        self.depth
+    }
+
+    pub fn dimensions(&self) -> Dimensions3 {
+        Dimensions3{ w: self.width, h: self.height, d: self.depth }
+    }
+
+    fn index<C: Coord>(&self, p: &Point3<C>) -> Option<usize> {
+        let (x, y, z) = (p.x.as_i64(), p.y.as_i64(), p.z.as_i64());
+        if x < 0 || x >= self.width || y < 0 || y >= self.height || z < 0 || z >= self.depth {
+            None
+        } else {
+            Some((x + (y + z * self.height) * self.width) as usize)
+        }
+    }
+
+    pub fn get<C: Coord>(&self, p: &Point3<C>) -> T {
+        match self.index(p) {
+            Some(index) => self.data[index],
+            None => panic!("Point {:?} out of bounds for {}x{}x{} grid", p, self.width, self.height, self.depth),
+        }
+    }
+
+    pub fn get_or_default<C: Coord>(&self, p: &Point3<C>, default: T) -> T {
+        match self.index(p) {
+            Some(index) => self.data[index],
+            None => default,
+        }
+    }
+
+    pub fn set<C: Coord>(&mut self, p: &Point3<C>, value: T) {
+        match self.index(p) {
+            Some(index) => self.data[index] = value,
+            None => panic!("Point {:?} out of bounds for {}x{}x{} grid", p, self.width, self.height, self.depth),
+        }
+    }
+
+    pub fn is_in_bounds<C: Coord>(&self, p: &Point3<C>) -> bool {
+        self.index(p).is_some()
+    }
+
+    // All points, x varying fastest and z slowest
+    pub fn points(&self) -> impl Iterator<Item = Point3> {
+        let (width, height) = (self.width, self.height);
+        (0..self.depth).flat_map(move |z| (0..height).flat_map(move |y| (0..width).map(move |x| Point3{ x, y, z })))
+    }
+
+    // `p` shifted by each of the offsets, skipping points outside the grid
+    pub fn stencil<'a, C: Coord>(&'a self, p: &Point3<C>, offsets: &'a [Vector3]) -> impl Iterator<Item = Point3<C>> + 'a {
+        let p = *p;
+        offsets.iter()
+            .map(move |offset| Point3{
+                x: p.x + C::from_i64(offset.xd),
+                y: p.y + C::from_i64(offset.yd),
+                z: p.z + C::from_i64(offset.zd),
+            })
+            .filter(move |neighbor| self.is_in_bounds(neighbor))
+    }
+
+    pub fn neighbors6<C: Coord>(&self, p: &Point3<C>) -> impl Iterator<Item = Point3<C>> + '_ {
+        self.stencil(p, &NEIGHBORS6)
+    }
+
+    // Points reachable from `start` through neighbors whose value satisfies
+    // `predicate`, in breadth-first order. Empty if `start` itself does not
+    // satisfy it.
+    pub fn flood_fill<PredicateFn: Fn(T) -> bool>(&self, start: &Point3, connectivity: Connectivity3,
+        predicate: PredicateFn) -> Vec<Point3>
+    {
+        let mut filled = Vec::new();
+        let mut seen = Grid3::new(self.width, self.height, self.depth, false);
+        let neighbors = |p: &Point3| self.stencil(p, connectivity.offsets());
+        breadth_first([*start], neighbors, |p, _| {
+            if seen.get(p) || !predicate(self.get(p)) {
+                return false;
+            }
+            seen.set(p, true);
+            filled.push(*p);
+            true
+        });
+        filled
+    }
+}
+
+impl <T: Copy + PartialEq> Grid3<T> {
+    // Smallest and largest corner (both inclusive) of the cells that differ from
+    // `background`
+    pub fn bounding_box(&self, background: T) -> Option<(Point3, Point3)> {
+        let filled: Vec<Point3> = self.points().filter(|p| self.get(p) != background).collect();
+        bounding_box3(&filled)
+    }
+}
+
+// 45 degree rotated coordinates (u, v) = (x + y, x - y), turning Manhattan
+// diamonds into axis-aligned squares. Only points with u and v of the same
+// parity correspond to grid points.
+pub fn rotate45(p: &Point) -> Point {
+    Point{ x: p.x + p.y, y: p.x - p.y }
+}
+
+pub fn unrotate45(p: &Point) -> Option<Point> {
+    if (p.x + p.y) % 2 != 0 {
+        return None;
+    }
+    Some(Point{ x: (p.x + p.y) / 2, y: (p.x - p.y) / 2 })
+}
+
+// Rectangle in rotated coordinates between two corners (both inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotatedRect {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl RotatedRect {
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y
+    }
+
+    // Whether grid point `p` is inside
+    pub fn contains(&self, p: &Point) -> bool {
+        let r = rotate45(p);
+        r.x >= self.min.x && r.x <= self.max.x && r.y >= self.min.y && r.y <= self.max.y
+    }
+
+    pub fn intersection(&self, other: &RotatedRect) -> Option<RotatedRect> {
+        let result = RotatedRect{
+            min: Point{ x: self.min.x.max(other.min.x), y: self.min.y.max(other.min.y) },
+            max: Point{ x: self.max.x.min(other.max.x), y: self.max.y.min(other.max.y) },
+        };
+        if result.is_empty() { None } else { Some(result) }
+    }
+
+    // Grid points inside, in ascending rotated coordinates
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (min, max) = (self.min, self.max);
+        (min.x..=max.x)
+            .flat_map(move |u| (min.y..=max.y).map(move |v| Point{ x: u, y: v }))
+            .filter_map(|r| unrotate45(&r))
+    }
+}
+
+// Cells within Manhattan distance `radius` of `center`, a diamond on the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ManhattanBall {
+    pub center: Point,
+    pub radius: i64,
+}
+
+impl ManhattanBall {
+    pub fn new(center: Point, radius: i64) -> ManhattanBall {
+        assert!(radius >= 0, "Negative radius {}", radius);
+        ManhattanBall{ center, radius }
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        (*p - self.center).l1_norm() <= self.radius
+    }
+
+    pub fn contains_ball(&self, other: &ManhattanBall) -> bool {
+        (other.center - self.center).l1_norm() + other.radius <= self.radius
+    }
+
+    pub fn intersects(&self, other: &ManhattanBall) -> bool {
+        (other.center - self.center).l1_norm() <= self.radius + other.radius
+    }
+
+    // The square the diamond becomes in rotated coordinates
+    pub fn to_rotated(self) -> RotatedRect {
+        let center = rotate45(&self.center);
+        let extent = Vector{ xd: self.radius, yd: self.radius };
+        RotatedRect{ min: center - extent, max: center + extent }
+    }
+
+    // Cells covered by both diamonds, as a rectangle in rotated coordinates
+    pub fn intersection(&self, other: &ManhattanBall) -> Option<RotatedRect> {
+        self.to_rotated().intersection(&other.to_rotated())
+    }
+
+    // Covered cells of row `y` as a half-open range of x coordinates
+    pub fn row_span(&self, y: i64) -> Option<std::ops::Range<i64>> {
+        let half_width = self.radius - (y - self.center.y).abs();
+        if half_width < 0 {
+            None
+        } else {
+            Some(self.center.x - half_width..self.center.x + half_width + 1)
+        }
+    }
+
+    // Cells at exactly distance `radius`, clockwise starting at the top
+    pub fn boundary(&self) -> impl Iterator<Item = Point> {
+        let ManhattanBall{ center, radius } = *self;
+        let sides = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+        let steps = if radius == 0 { 1 } else { 4 * radius };
+        let top = Point{ x: center.x, y: center.y - radius };
+        (0..steps).map(move |i| {
+            let (side, offset) = (i / radius.max(1), i % radius.max(1));
+            let corners = [top, Point{ x: center.x + radius, y: center.y },
+                Point{ x: center.x, y: center.y + radius }, Point{ x: center.x - radius, y: center.y }];
+            let (xd, yd) = sides[side as usize];
+            corners[side as usize] + Vector{ xd: xd * offset, yd: yd * offset }
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct GridParseError {
+    pub line_no: usize,
+    pub msg: String,
+}
+
+impl std::fmt::Display for GridParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line_no + 1, self.msg)
+    }
+}
+
+// Positions of special cells (like 'S' and 'E') seen while parsing a grid
+#[derive(Debug, Default)]
+pub struct Markers {
+    positions: HashMap<char, Vec<Point>>,
+}
+
+impl Markers {
+    pub fn mark(&mut self, ch: char, p: Point) {
+        self.positions.entry(ch).or_default().push(p);
+    }
+
+    pub fn all(&self, ch: char) -> &[Point] {
+        self.positions.get(&ch).map_or(&[], |points| &points[..])
+    }
+
+    pub fn one(&self, ch: char) -> Result<Point, String> {
+        match self.all(ch) {
+            [p] => Ok(*p),
+            points => Err(format!("Expected exactly one '{}' marker, but found {}", ch, points.len())),
+        }
+    }
+}
+
+impl <T: Copy> Grid<T> {
+    // Builds a grid from a character map given as numbered lines (as returned by
+    // `util::input_lines_indices`). Empty lines are skipped, all others must have
+    // the same length. `cell` turns each character into a value and can record
+    // markers on the way.
+    pub fn from_lines<I, S, CellFn>(lines: I, mut cell: CellFn) -> Result<Grid<T>, GridParseError>
+    where
+        I: IntoIterator<Item = (usize, S)>,
+        S: AsRef<str>,
+        CellFn: FnMut(char, Point) -> Result<T, String>
+    {
+        let mut data = Vec::new();
+        let mut width: Option<i64> = None;
+        let mut height = 0;
+        let mut last_line_no = 0;
+        for (line_no, line) in lines {
+            let line = line.as_ref();
+            last_line_no = line_no;
+            if line.is_empty() {
+                continue;
+            }
+            let line_width = line.chars().count() as i64;
+            match width {
+                None => width = Some(line_width),
+                Some(width) if width != line_width => return Err(GridParseError{
+                    line_no,
+                    msg: format!("Expected {} columns, but got {}", width, line_width),
+                }),
+                Some(_) => (),
+            }
+            for (x, ch) in line.chars().enumerate() {
+                let value = cell(ch, Point{ x: x as i64, y: height })
+                    .map_err(|msg| GridParseError{ line_no, msg: format!("column {}: {}", x + 1, msg) })?;
+                data.push(value);
+            }
+            height += 1;
+        }
+        match width {
+            None => Err(GridParseError{ line_no: last_line_no, msg: "Expected at least one line".to_owned() }),
+            Some(width) => Ok(Grid{ data, width, height }),
+        }
+    }
+
+    pub fn from_str_with<CellFn>(text: &str, cell: CellFn) -> Result<Grid<T>, GridParseError>
+    where
+        CellFn: FnMut(char, Point) -> Result<T, String>
+    {
+        Grid::from_lines(text.lines().enumerate(), cell)
+    }
+}
+
+// Foreground color for a cell when rendering with ANSI escapes, `None` keeps
+// the terminal's default
+pub type StyleFn<'a, T> = &'a dyn Fn(T) -> Option<crate::image::Rgb>;
+
+pub struct RenderOptions<'a, T> {
+    // Print x coordinates above and y coordinates left of the cells
+    pub labels: bool,
+    pub style: Option<StyleFn<'a, T>>,
+}
+
+impl <'a, T> Default for RenderOptions<'a, T> {
+    fn default() -> Self {
+        RenderOptions{ labels: false, style: None }
+    }
+}
+
+// Renders the cells from `from` towards `from + size` (exclusive), walking
+// backwards along an axis if `size` is negative there
+fn render_helper<T, W, GetterFn>(out: &mut W, from: &Point, size: &Vector, getter: GetterFn,
+    options: &RenderOptions<T>) -> std::fmt::Result
+where
+    T: Copy + std::fmt::Display,
+    W: std::fmt::Write,
+    GetterFn: Fn(&Point) -> T
+{
+    let step = size.signum();
+    let cols: Vec<i64> = (0..size.xd.abs()).map(|i| from.x + i * step.xd).collect();
+    let rows: Vec<i64> = (0..size.yd.abs()).map(|i| from.y + i * step.yd).collect();
+
+    let row_label_width = rows.iter().map(|y| y.to_string().len()).max().unwrap_or(0);
+    if options.labels {
+        let col_labels: Vec<String> = cols.iter().map(|x| x.to_string()).collect();
+        let label_height = col_labels.iter().map(|label| label.len()).max().unwrap_or(0);
+        for line in 0..label_height {
+            write!(out, "{:width$} ", "", width = row_label_width)?;
+            for label in col_labels.iter() {
+                let padding = label_height - label.len();
+                let ch = if line < padding { ' ' } else { label.as_bytes()[line - padding] as char };
+                write!(out, "{}", ch)?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    for row in rows.iter() {
+        if options.labels {
+            write!(out, "{:>width$} ", row, width = row_label_width)?;
+        }
+        for col in cols.iter() {
+            let value = getter(&Point{ x: *col, y: *row });
+            match options.style.and_then(|style| style(value)) {
+                Some(crate::image::Rgb(r, g, b)) => write!(out, "\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, value)?,
+                None => write!(out, "{}", value)?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+impl <T: Copy + std::fmt::Display> Grid<T> {
+    pub fn render<W: std::fmt::Write>(&self, out: &mut W, options: &RenderOptions<T>) -> std::fmt::Result {
+        self.render_part(out, &Point{ x: 0, y: 0 }, &Vector{ xd: self.width, yd: self.height }, options)
+    }
+
+    pub fn render_part<W: std::fmt::Write>(&self, out: &mut W, from: &Point, size: &Vector,
+        options: &RenderOptions<T>) -> std::fmt::Result
+    {
+        render_helper(out, from, size, |point| self.get(point), options)
+    }
+
+    pub fn render_part_default<W: std::fmt::Write>(&self, out: &mut W, from: &Point, size: &Vector, default: T,
+        options: &RenderOptions<T>) -> std::fmt::Result
+    {
+        render_helper(out, from, size, |point| self.get_or_default(point, default), options)
+    }
+
+    pub fn write_to<W: std::io::Write>(&self, out: &mut W, options: &RenderOptions<T>) -> std::io::Result<()> {
+        let mut text = String::new();
+        self.render(&mut text, options).unwrap();
+        out.write_all(text.as_bytes())
+    }
+
+    pub fn dump(&self) {
+        self.write_to(&mut std::io::stdout(), &RenderOptions::default()).unwrap();
+    }
+
+    pub fn dump_part(&self, from: &Point, size: &Vector) {
+        assert!(self.is_in_bounds(from));
+        assert!(self.is_in_bounds(&(*from + *size - size.signum())));
+
+        let mut text = String::new();
+        self.render_part(&mut text, from, size, &RenderOptions::default()).unwrap();
+        print!("{}", text);
+    }
+
+    pub fn dump_part_default(&self, from: &Point, size: &Vector, default: T) {
+        let mut text = String::new();
+        self.render_part_default(&mut text, from, size, default, &RenderOptions::default()).unwrap();
+        print!("{}", text);
+    }
+}
+
+impl <T: Copy + std::fmt::Display + PartialEq> Grid<T> {
+    // Smallest and largest corner (both inclusive) of the cells that differ from
+    // `background`
+    pub fn bounding_box(&self, background: T) -> Option<(Point, Point)> {
+        self.points()
+            .filter(|p| self.get(p) != background)
+            .fold(None, |bounds, p| match bounds {
+                None => Some((p, p)),
+                Some((min, max)) => Some((
+                    Point{ x: min.x.min(p.x), y: min.y.min(p.y) },
+                    Point{ x: max.x.max(p.x), y: max.y.max(p.y) },
+                )),
+            })
+    }
+
+    // Like `render`, but leaves out the margins that only contain `background`
+    pub fn render_cropped<W: std::fmt::Write>(&self, out: &mut W, background: T,
+        options: &RenderOptions<T>) -> std::fmt::Result
+    {
+        match self.bounding_box(background) {
+            None => Ok(()),
+            Some((min, max)) => {
+                let size = max - min + Vector{ xd: 1, yd: 1 };
+                self.render_part(out, &min, &size, options)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_arithmetic() {
+        let p = Point{ x: 3, y: -2 };
+        let v = Vector{ xd: -5, yd: 4 };
+        assert_eq!(p + v, Point{ x: -2, y: 2 });
+        assert_eq!(p - v, Point{ x: 8, y: -6 });
+        assert_eq!(Point{ x: -2, y: 2 } - p, v);
+        assert_eq!(v.l1_norm(), 9);
+        assert_eq!(v.signum(), Vector{ xd: -1, yd: 1 });
+
+        let small: Point<i32> = Point{ x: 1, y: 1 };
+        assert_eq!(small + Vector{ xd: 1, yd: -1 }, Point{ x: 2, y: 0 });
+    }
+
+    #[test]
+    fn test_vector_arithmetic() {
+        let v = Vector{ xd: -5, yd: 4 };
+        let w: Vector = (2, 3).into();
+        assert_eq!(v + w, Vector{ xd: -3, yd: 7 });
+        assert_eq!(v - w, Vector{ xd: -7, yd: 1 });
+        assert_eq!(v * 3, Vector{ xd: -15, yd: 12 });
+        assert_eq!(-v, Vector{ xd: 5, yd: -4 });
+        assert_eq!(v.linf_norm(), 5);
+        assert_eq!(Vector{ xd: 3, yd: -4 }.l2_norm(), 5.0);
+
+        let mut p: Point = (1, 1).into();
+        p += v;
+        p -= w;
+        assert_eq!(p, Point{ x: -6, y: 2 });
+        let mut u = Vector::default();
+        u += w;
+        u -= v;
+        assert_eq!(u, Vector{ xd: 7, yd: -1 });
+
+        assert_eq!(Point::<i64>::default(), Point{ x: 0, y: 0 });
+        assert_eq!(format!("{} {}", p, v), "(-6, 2) <-5, 4>");
+    }
+
+    #[test]
+    fn test_directions() {
+        let up = Direction4::Up;
+        assert_eq!(up.turn_right(), Direction4::Right);
+        assert_eq!(up.turn_left(), Direction4::Left);
+        assert_eq!(Direction4::Left.opposite(), Direction4::Right);
+        assert_eq!(up.as_vector(YAxis::Down), Vector{ xd: 0, yd: -1 });
+        assert_eq!(up.as_vector(YAxis::Up), Vector{ xd: 0, yd: 1 });
+        assert_eq!(Direction4::from_vector(&Vector{ xd: 0, yd: 1 }, YAxis::Up), Some(Direction4::Up));
+        assert_eq!(Direction4::from_vector(&Vector::<i32>{ xd: -1, yd: 0 }, YAxis::Down), Some(Direction4::Left));
+        assert_eq!(Direction4::from_vector(&Vector{ xd: 1, yd: 1 }, YAxis::Down), None);
+        assert_eq!("R".parse::<Direction4>(), Ok(Direction4::Right));
+        assert_eq!("v".parse::<Direction4>(), Ok(Direction4::Down));
+        assert_eq!(Direction4::from_char('←'), Some(Direction4::Left));
+        assert!("RR".parse::<Direction4>().is_err());
+        assert_eq!(Direction4::Down.to_string(), "v");
+
+        let diagonal = Direction8::DownLeft;
+        assert_eq!(diagonal.turn_right(), Direction8::Left);
+        assert_eq!(diagonal.turn_left(), Direction8::Down);
+        assert_eq!(diagonal.opposite(), Direction8::UpRight);
+        assert_eq!(diagonal.as_vector(YAxis::Down), Vector{ xd: -1, yd: 1 });
+        assert_eq!(Direction8::from(Direction4::Left), Direction8::Left);
+        assert_eq!("NE".parse::<Direction8>(), Ok(Direction8::UpRight));
+        assert_eq!("↙".parse::<Direction8>(), Ok(Direction8::DownLeft));
+        assert!("RU".parse::<Direction8>().is_err());
+    }
+
+    #[test]
+    fn test_grid_access() {
+        let mut grid = Grid::new(3, 2, 0);
+        grid.set(&Point{ x: 2, y: 1 }, 5);
+        grid.set(&Point::<i32>{ x: 0, y: 1 }, 4);
+
+        assert_eq!(grid.get(&Point{ x: 2, y: 1 }), 5);
+        assert_eq!(grid.get(&Point::<i8>{ x: 0, y: 1 }), 4);
+        assert_eq!(grid.get_or_default(&Point{ x: 3, y: 1 }, -1), -1);
+        assert!(!grid.is_in_bounds(&Point{ x: 0, y: -1 }));
+        assert_eq!(grid.points().count(), 6);
+    }
+
+    #[test]
+    fn test_grid_rows_cols() {
+        let mut grid = Grid::new(3, 2, 0);
+        for p in grid.points() {
+            grid.set(&p, p.x + 10 * p.y);
+        }
+
+        let row: Vec<i64> = grid.row(1).map(|(_, value)| value).collect();
+        assert_eq!(row, vec![10, 11, 12]);
+        let col: Vec<(Point, i64)> = grid.col(2).rev().collect();
+        assert_eq!(col, vec![(Point{ x: 2, y: 1 }, 12), (Point{ x: 2, y: 0 }, 2)]);
+    }
+
+    #[test]
+    fn test_grid_neighbors() {
+        let grid = Grid::new(3, 3, 0);
+        let corner: Vec<Point> = grid.neighbors4(&Point{ x: 0, y: 0 }).collect();
+        assert_eq!(corner, vec![Point{ x: 1, y: 0 }, Point{ x: 0, y: 1 }]);
+        assert_eq!(grid.neighbors4(&Point{ x: 1, y: 1 }).count(), 4);
+        assert_eq!(grid.neighbors8(&Point{ x: 1, y: 1 }).count(), 8);
+        assert_eq!(grid.neighbors8(&Point::<i32>{ x: 2, y: 0 }).count(), 3);
+
+        let knight = [Vector{ xd: 1, yd: 2 }, Vector{ xd: -1, yd: 2 }, Vector{ xd: 2, yd: 1 }];
+        let jumps: Vec<Point> = grid.stencil(&Point{ x: 0, y: 0 }, &knight).collect();
+        assert_eq!(jumps, vec![Point{ x: 1, y: 2 }, Point{ x: 2, y: 1 }]);
+    }
+
+    #[test]
+    fn test_grid_ray() {
+        let mut grid = Grid::new(4, 4, 0);
+        for p in grid.points() {
+            grid.set(&p, p.x + 10 * p.y);
+        }
+
+        let diagonal: Vec<i64> = grid.ray(&Point{ x: 0, y: 0 }, Vector{ xd: 1, yd: 1 })
+            .map(|(_, value)| value).collect();
+        assert_eq!(diagonal, vec![11, 22, 33]);
+        assert_eq!(grid.ray(&Point{ x: 0, y: 2 }, Vector{ xd: -1, yd: 0 }).count(), 0);
+    }
+
+    #[test]
+    fn test_infinite_grid() {
+        let mut grid = InfiniteGrid::new('.');
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(grid.get(&Point{ x: 1000, y: -1000 }), '.');
+
+        grid.set(&Point{ x: -40, y: 3 }, '#');
+        grid.set(&Point::<i32>{ x: 2, y: -1 }, 'o');
+        assert_eq!(grid.get(&Point{ x: -40, y: 3 }), '#');
+        assert_eq!(grid.get(&Point{ x: 2, y: -1 }), 'o');
+        assert_eq!(grid.get(&Point{ x: -39, y: 3 }), '.');
+        assert_eq!(grid.bounds(), Some((Point{ x: -40, y: -1 }, Point{ x: 2, y: 3 })));
+        assert_eq!(grid.get_or_default(&Point{ x: 0, y: 0 }, '~'), '.');
+        assert_eq!(grid.get_or_default(&Point{ x: 0, y: 4 }, '~'), '~');
+
+        let (dense, origin) = grid.to_grid().unwrap();
+        assert_eq!(origin, Point{ x: -40, y: -1 });
+        assert_eq!((dense.width(), dense.height()), (43, 5));
+        assert_eq!(dense.get(&Point{ x: 0, y: 4 }), '#');
+        assert_eq!(dense.get(&Point{ x: 42, y: 0 }), 'o');
+        for y in -3..6 {
+            for x in -42..5 {
+                let p = Point{ x, y };
+                assert_eq!(grid.get_or_default(&p, '~'), dense.get_or_default(&(p - origin.as_vector()), '~'));
+            }
+        }
+
+        // Writing further out moves the box over points that read as `default`
+        grid.set(&Point{ x: 2, y: 6 }, '#');
+        assert_eq!(grid.get_or_default(&Point{ x: 0, y: 4 }, '~'), '.');
+    }
+
+    #[test]
+    fn test_grid_render() {
+        let mut grid = Grid::new(40, 12, '.');
+        grid.set(&Point{ x: 8, y: 9 }, '#');
+        grid.set(&Point{ x: 11, y: 10 }, 'o');
+
+        let mut text = String::new();
+        grid.render(&mut text, &RenderOptions::default()).unwrap();
+        assert_eq!(text.lines().count(), 12);
+        assert!(text.lines().all(|line| line.len() == 40));
+
+        let mut text = String::new();
+        grid.render_cropped(&mut text, '.', &RenderOptions{ labels: true, ..Default::default() }).unwrap();
+        assert_eq!(text, "     11\n   8901\n 9 #...\n10 ...o\n");
+
+        let style = |cell: char| if cell == '#' { Some(crate::image::Rgb(255, 0, 0)) } else { None };
+        let mut text = String::new();
+        grid.render_part(&mut text, &Point{ x: 9, y: 9 }, &Vector{ xd: -2, yd: 1 },
+            &RenderOptions{ style: Some(&style), ..Default::default() }).unwrap();
+        assert_eq!(text, ".\x1b[38;2;255;0;0m#\x1b[0m\n");
+    }
+
+    #[test]
+    fn test_grid_from_str() {
+        let mut markers = Markers::default();
+        let grid = Grid::from_str_with("Sab\nbcE\n", |ch, p| match ch {
+            'S' | 'E' => {
+                markers.mark(ch, p);
+                Ok(0)
+            },
+            'a'..='z' => Ok(ch as u32 - 'a' as u32),
+            _ => Err(format!("unexpected '{}'", ch)),
+        }).unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(&Point{ x: 1, y: 1 }), 2);
+        assert_eq!(markers.one('S'), Ok(Point{ x: 0, y: 0 }));
+        assert_eq!(markers.one('E'), Ok(Point{ x: 2, y: 1 }));
+        assert!(markers.one('X').is_err());
+
+        let digits = |ch: char, _| ch.to_digit(10).ok_or(format!("unexpected '{}'", ch));
+        assert_matches!(Grid::from_str_with("12\n345\n", digits).err(), Some(GridParseError{ line_no: 1, .. }));
+        assert_matches!(Grid::from_str_with("12\n\n3x\n", digits).err(), Some(GridParseError{ line_no: 2, .. }));
+        assert!(Grid::from_str_with("\n", digits).is_err());
+    }
+
+    #[test]
+    fn test_grid_transformations() {
+        let grid: Grid<char> = Grid::from_str_with("abc\ndef", |ch, _| Ok(ch)).unwrap();
+        let text = |grid: &Grid<char>| {
+            let mut text = String::new();
+            grid.render(&mut text, &RenderOptions::default()).unwrap();
+            text
+        };
+        assert_eq!(text(&grid.transpose()), "ad\nbe\ncf\n");
+        assert_eq!(text(&grid.rotate90()), "da\neb\nfc\n");
+        assert_eq!(text(&grid.rotate90().rotate90()), "fed\ncba\n");
+        assert_eq!(text(&grid.flip_x()), "cba\nfed\n");
+        assert_eq!(text(&grid.flip_y()), "def\nabc\n");
+        assert_eq!(text(&grid.as_view().rotate90().rotate90().rotate90().rotate90().to_grid()), text(&grid));
+    }
+
+    #[test]
+    fn test_grid_view() {
+        let grid: Grid<char> = Grid::from_str_with("abcd\nefgh\nijkl", |ch, _| Ok(ch)).unwrap();
+        let view = grid.view(&Point{ x: 1, y: 1 }, &Vector{ xd: 3, yd: 2 });
+        assert_eq!((view.width(), view.height()), (3, 2));
+        assert_eq!(view.get(&Point{ x: 0, y: 0 }), 'f');
+        assert_eq!(view.get_or_default(&Point{ x: 3, y: 0 }, '?'), '?');
+        assert_eq!(view.row(1).map(|(_, ch)| ch).collect::<String>(), "jkl");
+        assert_eq!(view.col(2).rev().map(|(_, ch)| ch).collect::<String>(), "lh");
+
+        let rotated = view.rotate90();
+        assert_eq!(rotated.row(0).map(|(_, ch)| ch).collect::<String>(), "jf");
+        assert_eq!(rotated.grid_point(&Point{ x: 0, y: 2 }), Point{ x: 3, y: 2 });
+        let inner = rotated.view(&Point{ x: 1, y: 1 }, &Vector{ xd: 1, yd: 2 });
+        assert_eq!(inner.col(0).map(|(_, ch)| ch).collect::<String>(), "gh");
+    }
+
+    #[test]
+    fn test_grid3() {
+        let p = Point3{ x: 1, y: 2, z: 3 };
+        let v = Vector3{ xd: -1, yd: 0, zd: 2 };
+        assert_eq!(p + v, Point3{ x: 0, y: 2, z: 5 });
+        assert_eq!((p + v) - p, v);
+        assert_eq!(v.l1_norm(), 3);
+
+        let mut grid = Grid3::new(2, 3, 4, 0);
+        grid.set(&p, 7);
+        assert_eq!(grid.get(&p), 7);
+        assert_eq!(grid.get_or_default(&Point3{ x: 2, y: 0, z: 0 }, -1), -1);
+        assert_eq!(grid.points().count(), 24);
+        assert_eq!(grid.points().position(|q| q == p), Some(1 + 2 * 2 + 3 * 6));
+        assert_eq!(grid.neighbors6(&p).count(), 3);
+        assert_eq!(grid.neighbors6(&Point3{ x: 0, y: 1, z: 1 }).count(), 5);
+        assert_eq!(grid.bounding_box(0), Some((p, p)));
+        assert_eq!(bounding_box3(&[p, Point3{ x: 3, y: -1, z: 3 }]), Some((Point3{ x: 1, y: -1, z: 3 }, Point3{ x: 3, y: 2, z: 3 })));
+    }
+
+    #[test]
+    fn test_grid3_flood_fill() {
+        // Hollow 3x3x3 cube inside a 5x5x5 grid
+        let mut grid = Grid3::new(5, 5, 5, false);
+        for p in grid.points().collect::<Vec<_>>() {
+            let inside = |c: i64| (1..=3).contains(&c);
+            if inside(p.x) && inside(p.y) && inside(p.z) {
+                grid.set(&p, true);
+            }
+        }
+        let center = Point3{ x: 2, y: 2, z: 2 };
+        grid.set(&center, false);
+
+        let outside = grid.flood_fill(&Point3{ x: 0, y: 0, z: 0 }, Connectivity3::Six, |solid| !solid);
+        assert_eq!(outside.len(), 125 - 27);
+        assert!(!outside.contains(&center));
+        assert_eq!(grid.flood_fill(&center, Connectivity3::TwentySix, |solid| !solid), vec![center]);
+        assert!(grid.flood_fill(&Point3{ x: 1, y: 1, z: 1 }, Connectivity3::Six, |solid| !solid).is_empty());
+
+        // Cells touching only along an edge or at a corner
+        let mut grid = Grid3::new(3, 3, 3, false);
+        for p in [Point3{ x: 0, y: 0, z: 0 }, Point3{ x: 1, y: 1, z: 0 }, Point3{ x: 2, y: 2, z: 1 }] {
+            grid.set(&p, true);
+        }
+        let start = Point3{ x: 0, y: 0, z: 0 };
+        assert_eq!(grid.flood_fill(&start, Connectivity3::Six, |solid| solid).len(), 1);
+        assert_eq!(grid.flood_fill(&start, Connectivity3::Eighteen, |solid| solid).len(), 2);
+        assert_eq!(grid.flood_fill(&start, Connectivity3::TwentySix, |solid| solid).len(), 3);
+        assert_eq!(NEIGHBORS18.iter().filter(|offset| offset.l1_norm() == 1).count(), 6);
+    }
+
+    #[test]
+    fn test_grid_regions() {
+        let grid: Grid<char> = Grid::from_str_with("##..#\n#..##\n..#..", |ch, _| Ok(ch)).unwrap();
+        let is_rock = |ch| ch == '#';
+
+        let filled = grid.flood_fill(&Point{ x: 0, y: 0 }, Connectivity::Four, is_rock);
+        assert_eq!(filled, vec![Point{ x: 0, y: 0 }, Point{ x: 1, y: 0 }, Point{ x: 0, y: 1 }]);
+        assert_eq!(grid.flood_fill(&Point{ x: 4, y: 0 }, Connectivity::Eight, is_rock).len(), 4);
+        assert!(grid.flood_fill(&Point{ x: 2, y: 0 }, Connectivity::Four, is_rock).is_empty());
+
+        let (labels, sizes) = grid.label_components(Connectivity::Four, is_rock);
+        assert_eq!(sizes, vec![3, 3, 1]);
+        assert_eq!(labels.get(&Point{ x: 3, y: 1 }), Some(1));
+        assert_eq!(labels.get(&Point{ x: 2, y: 2 }), Some(2));
+        assert_eq!(labels.get(&Point{ x: 2, y: 0 }), None);
+
+        let (_, sizes) = grid.label_components(Connectivity::Eight, is_rock);
+        assert_eq!(sizes, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_bit_grid() {
+        let mut grid = BitGrid::new(7);
+        assert_eq!(grid.height(), 0);
+        grid.set(&Point{ x: 6, y: 2 }, true);
+        assert_eq!(grid.height(), 3);
+        assert!(grid.get(&Point{ x: 6, y: 2 }));
+        assert!(!grid.get(&Point{ x: 6, y: 5 }));
+        assert_eq!(grid.row_mask(2), 0b1000000);
+
+        let block = [0b11, 0b11];
+        assert!(!grid.collides(&block, &Point{ x: 0, y: 0 }));
+        assert!(grid.collides(&block, &Point{ x: 5, y: 1 }));
+        assert!(grid.collides(&block, &Point{ x: 6, y: 5 }));
+        assert!(grid.collides(&block, &Point{ x: -1, y: 5 }));
+        assert!(grid.collides(&block, &Point{ x: 0, y: -1 }));
+
+        grid.place(&block, &Point{ x: 1, y: 3 });
+        assert_eq!(grid.height(), 5);
+        assert_eq!(grid.row_mask(4), 0b110);
+        assert!(grid.collides(&[0b1], &Point{ x: 2, y: 3 }));
+        assert_eq!(grid.to_grid().points().filter(|p| grid.get(p)).count(), 5);
+
+        grid.set(&Point{ x: 1, y: 3 }, false);
+        assert!(!grid.collides(&[0b1], &Point{ x: 1, y: 3 }));
+        assert!(!BitGrid::new(64).collides(&[1 << 63], &Point{ x: 0, y: 0 }));
+        assert!(BitGrid::new(64).collides(&[1 << 63], &Point{ x: 1, y: 0 }));
+    }
+
+    #[test]
+    fn test_manhattan_ball() {
+        let ball = ManhattanBall::new(Point{ x: 6, y: 3 }, 3);
+        assert!(ball.contains(&Point{ x: 4, y: 4 }));
+        assert!(!ball.contains(&Point{ x: 3, y: 4 }));
+        assert_eq!(ball.row_span(0), Some(6..7));
+        assert_eq!(ball.row_span(4), Some(4..9));
+        assert_eq!(ball.row_span(7), None);
+
+        let boundary: Vec<Point> = ball.boundary().collect();
+        assert_eq!(boundary.len(), 12);
+        assert_eq!(boundary[..4], [Point{ x: 6, y: 0 }, Point{ x: 7, y: 1 }, Point{ x: 8, y: 2 }, Point{ x: 9, y: 3 }]);
+        assert!(boundary.iter().all(|p| (*p - ball.center).l1_norm() == 3));
+        assert_eq!(boundary.iter().collect::<std::collections::HashSet<_>>().len(), 12);
+        assert_eq!(ManhattanBall::new(Point{ x: 1, y: 1 }, 0).boundary().collect::<Vec<_>>(), vec![Point{ x: 1, y: 1 }]);
+
+        let small = ManhattanBall::new(Point{ x: 7, y: 3 }, 1);
+        let far = ManhattanBall::new(Point{ x: 12, y: 3 }, 1);
+        assert!(ball.contains_ball(&small) && !small.contains_ball(&ball));
+        assert!(ball.intersects(&small) && !ball.intersects(&far));
+        assert_eq!(ball.intersection(&far), None);
+
+        // Both balls cover exactly the cells the rotated intersection contains
+        let other = ManhattanBall::new(Point{ x: 9, y: 5 }, 2);
+        let overlap = ball.intersection(&other).unwrap();
+        let expected: Vec<Point> = Grid::new(15, 10, ()).points().filter(|p| ball.contains(p) && other.contains(p)).collect();
+        let mut points: Vec<Point> = overlap.points().collect();
+        points.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(points, expected);
+        assert!(expected.iter().all(|p| overlap.contains(p)));
+
+        let p = Point{ x: -3, y: 5 };
+        assert_eq!(unrotate45(&rotate45(&p)), Some(p));
+        assert_eq!(unrotate45(&Point{ x: 1, y: 2 }), None);
+    }
+}
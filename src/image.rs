@@ -0,0 +1,453 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::grid::Grid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+pub trait ToColor {
+    fn to_color(&self) -> Rgb;
+}
+
+impl ToColor for bool {
+    fn to_color(&self) -> Rgb {
+        if *self { Rgb(255, 255, 255) } else { Rgb(0, 0, 0) }
+    }
+}
+
+// Blank-ish characters are dark, everything else gets a stable color picked by
+// its code point, so characters used for different things are told apart
+impl ToColor for char {
+    fn to_color(&self) -> Rgb {
+        match self {
+            ' ' | '.' => Rgb(20, 20, 30),
+            '#' => Rgb(230, 230, 230),
+            ch => {
+                let hash = (*ch as u32).wrapping_mul(2654435761);
+                Rgb(96 + (hash >> 24) as u8 % 160, 96 + (hash >> 16) as u8 % 160, 96 + (hash >> 8) as u8 % 160)
+            }
+        }
+    }
+}
+
+impl ToColor for Rgb {
+    fn to_color(&self) -> Rgb {
+        *self
+    }
+}
+
+// Blue to red gradient for heat maps, `value` is clamped to [min, max]
+pub fn heat(value: f64, min: f64, max: f64) -> Rgb {
+    let t = if max > min { ((value - min) / (max - min)).clamp(0.0, 1.0) } else { 0.0 };
+    let channel = |x: f64| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb(channel(2.0 * t - 0.5), channel(1.0 - (2.0 * t - 1.0).abs()), channel(1.5 - 2.0 * t))
+}
+
+impl <T: Copy> Grid<T> {
+    fn rgb_rows<ColorFn: Fn(T) -> Rgb>(&self, color: ColorFn) -> Vec<Vec<u8>> {
+        (0..self.height())
+            .map(|y| self.row(y).flat_map(|(_, value)| {
+                let Rgb(r, g, b) = color(value);
+                [r, g, b]
+            }).collect())
+            .collect()
+    }
+
+    // Binary PPM (P6), one pixel per cell
+    pub fn write_ppm_with<W: Write, ColorFn: Fn(T) -> Rgb>(&self, mut out: W, color: ColorFn) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width(), self.height())?;
+        for row in self.rgb_rows(color) {
+            out.write_all(&row)?;
+        }
+        out.flush()
+    }
+
+    // 8-bit RGB PNG, one pixel per cell
+    pub fn write_png_with<W: Write, ColorFn: Fn(T) -> Rgb>(&self, mut out: W, color: ColorFn) -> io::Result<()> {
+        let mut header = Vec::new();
+        header.extend((self.width() as u32).to_be_bytes());
+        header.extend((self.height() as u32).to_be_bytes());
+        // bit depth 8, color type RGB, default compression/filter, no interlace
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut scanlines = Vec::new();
+        for row in self.rgb_rows(color) {
+            scanlines.push(0);
+            scanlines.extend(row);
+        }
+
+        out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+        write_png_chunk(&mut out, b"IHDR", &header)?;
+        write_png_chunk(&mut out, b"IDAT", &zlib_stored(&scanlines))?;
+        write_png_chunk(&mut out, b"IEND", &[])?;
+        out.flush()
+    }
+
+    // Writes a .png or .ppm file, depending on the extension of `path`
+    pub fn save_image_with<ColorFn: Fn(T) -> Rgb>(&self, path: &str, color: ColorFn) -> io::Result<()> {
+        let out = BufWriter::new(File::create(path)?);
+        if path.ends_with(".ppm") {
+            self.write_ppm_with(out, color)
+        } else if path.ends_with(".png") {
+            self.write_png_with(out, color)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown image format: {}", path)))
+        }
+    }
+}
+
+impl <T: Copy + ToColor> Grid<T> {
+    pub fn write_ppm<W: Write>(&self, out: W) -> io::Result<()> {
+        self.write_ppm_with(out, |value| value.to_color())
+    }
+
+    pub fn write_png<W: Write>(&self, out: W) -> io::Result<()> {
+        self.write_png_with(out, |value| value.to_color())
+    }
+
+    pub fn save_image(&self, path: &str) -> io::Result<()> {
+        self.save_image_with(path, |value| value.to_color())
+    }
+}
+
+fn write_png_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&crc.finish().to_be_bytes())
+}
+
+struct Crc32 {
+    table: [u32; 256],
+    value: u32,
+}
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        let mut table = [0; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+            }
+            *entry = c;
+        }
+        Crc32{ table, value: 0xffffffff }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.value = self.table[((self.value ^ *byte as u32) & 0xff) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.value ^ 0xffffffff
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// zlib stream made of uncompressed deflate blocks. Images are small enough that
+// compressing them is not worth an encoder.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+    let mut result = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        result.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        result.push(if last { 1 } else { 0 });
+        result.extend((block.len() as u16).to_le_bytes());
+        result.extend((!(block.len() as u16)).to_le_bytes());
+        result.extend(block);
+    }
+    result.extend(adler32(data).to_be_bytes());
+    result
+}
+
+// Animated GIF looping forever, written one frame at a time. Each frame is
+// `width * height` pixels in row-major order with its own palette, so a frame
+// may use at most 256 different colors but the whole animation more.
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    delay_cs: u16,
+}
+
+impl <W: Write> GifWriter<W> {
+    pub fn new(mut out: W, width: u16, height: u16, delay_cs: u16) -> io::Result<GifWriter<W>> {
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        out.write_all(&[0, 0, 0])?;
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(GifWriter{ out, width, height, delay_cs })
+    }
+
+    pub fn frame(&mut self, pixels: &[Rgb]) -> io::Result<()> {
+        assert!(pixels.len() == self.width as usize * self.height as usize);
+        let mut palette: Vec<Rgb> = Vec::new();
+        let mut palette_index: HashMap<Rgb, u8> = HashMap::new();
+        let mut indices = Vec::with_capacity(pixels.len());
+        for color in pixels {
+            let index = match palette_index.get(color) {
+                Some(index) => *index,
+                None => {
+                    if palette.len() == 256 {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, "GIF frame uses more than 256 colors"));
+                    }
+                    palette.push(*color);
+                    palette_index.insert(*color, (palette.len() - 1) as u8);
+                    (palette.len() - 1) as u8
+                }
+            };
+            indices.push(index);
+        }
+
+        let mut table_bits = 1;
+        while (1 << table_bits) < palette.len() {
+            table_bits += 1;
+        }
+        palette.resize(1 << table_bits, Rgb(0, 0, 0));
+
+        let out = &mut self.out;
+        out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        out.write_all(&self.delay_cs.to_le_bytes())?;
+        out.write_all(&[0x00, 0x00])?;
+
+        out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        out.write_all(&self.width.to_le_bytes())?;
+        out.write_all(&self.height.to_le_bytes())?;
+        out.write_all(&[0x80 | (table_bits - 1)])?;
+        for Rgb(r, g, b) in palette.iter() {
+            out.write_all(&[*r, *g, *b])?;
+        }
+        let min_code_size = table_bits.max(2);
+        out.write_all(&[min_code_size])?;
+        for block in lzw_encode(&indices, min_code_size).chunks(255) {
+            out.write_all(&[block.len() as u8])?;
+            out.write_all(block)?;
+        }
+        out.write_all(&[0x00])
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+pub fn write_gif<W: Write>(out: W, width: u16, height: u16, frames: &[Vec<Rgb>], delay_cs: u16) -> io::Result<()> {
+    let mut gif = GifWriter::new(out, width, height, delay_cs)?;
+    for frame in frames {
+        gif.frame(frame)?;
+    }
+    gif.finish().map(|_| ())
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size as u32;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+// Variable-length-code LZW as used by GIF, restarting with a clear code once
+// the 12 bit code table is full
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    const MAX_CODE: u16 = 4096;
+    let clear: u16 = 1 << min_code_size;
+    let end: u16 = clear + 1;
+
+    let mut writer = BitWriter{ bytes: Vec::new(), acc: 0, bits: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = min_code_size + 1;
+
+    writer.write(clear, code_size);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let current = match prefix {
+            None => index as u16,
+            Some(prefix) => match table.get(&(prefix, index)) {
+                Some(code) => *code,
+                None => {
+                    writer.write(prefix, code_size);
+                    if next_code < MAX_CODE {
+                        table.insert((prefix, index), next_code);
+                        if next_code == 1 << code_size {
+                            code_size += 1;
+                        }
+                        next_code += 1;
+                    } else {
+                        writer.write(clear, code_size);
+                        table.clear();
+                        next_code = end + 1;
+                        code_size = min_code_size + 1;
+                    }
+                    index as u16
+                }
+            }
+        };
+        prefix = Some(current);
+    }
+    if let Some(prefix) = prefix {
+        writer.write(prefix, code_size);
+    }
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Point;
+
+    #[test]
+    fn test_checksums() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xcbf43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn test_zlib_stored() {
+        let data = vec![7u8; 70000];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream.len(), 2 + 2 * 5 + data.len() + 4);
+        assert_eq!(&stream[2..7], &[0, 0xff, 0xff, 0, 0]);
+        assert_eq!(stream[2 + 5 + 0xffff], 1);
+    }
+
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1u16 << min_code_size;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut result = Vec::new();
+        let mut previous: Option<Vec<u8>> = None;
+        let (mut acc, mut bits, mut pos) = (0u32, 0u8, 0);
+        loop {
+            while bits < code_size {
+                acc |= (data[pos] as u32) << bits;
+                pos += 1;
+                bits += 8;
+            }
+            let code = (acc & ((1 << code_size) - 1)) as u16;
+            acc >>= code_size;
+            bits -= code_size;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).chain([vec![], vec![]]).collect();
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return result;
+            }
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [&previous[..], &previous[..1]].concat(),
+                (None, None) => panic!("invalid code"),
+            };
+            result.extend(&entry);
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    table.push([&previous[..], &entry[..1]].concat());
+                    if table.len() == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let mut noise: Vec<u8> = Vec::new();
+        let mut state = 12345u32;
+        for _ in 0..50000 {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            noise.push((state >> 16) as u8 % 7);
+        }
+        for (data, min_code_size) in [(vec![0, 0, 0, 0, 1, 1, 0, 1], 2), (vec![3; 20000], 2), (noise, 3)] {
+            assert_eq!(lzw_decode(&lzw_encode(&data, min_code_size), min_code_size), data);
+        }
+    }
+
+    #[test]
+    fn test_write_gif() {
+        let red = Rgb(255, 0, 0);
+        let blue = Rgb(0, 0, 255);
+        let frames = vec![vec![red, blue, blue, red], vec![blue; 4]];
+        let mut gif = Vec::new();
+        write_gif(&mut gif, 2, 2, &frames, 10).unwrap();
+        assert_eq!(&gif[..13], b"GIF89a\x02\x00\x02\x00\x00\x00\x00");
+        // Local palette of the first frame after its image descriptor
+        let descriptor = gif.iter().position(|byte| *byte == 0x2c).unwrap();
+        assert_eq!(gif[descriptor + 9], 0x80);
+        assert_eq!(&gif[descriptor + 10..descriptor + 16], &[255, 0, 0, 0, 0, 255]);
+        assert_eq!(gif.iter().filter(|byte| **byte == 0x2c).count(), 2);
+        assert_eq!(*gif.last().unwrap(), 0x3b);
+
+        let too_many: Vec<Rgb> = (0..300).map(|i| Rgb(i as u8, (i / 256) as u8, 0)).collect();
+        assert!(write_gif(&mut Vec::new(), 300, 1, &[too_many], 10).is_err());
+    }
+
+    #[test]
+    fn test_write_images() {
+        let mut grid = Grid::new(2, 1, false);
+        grid.set(&Point{ x: 1, y: 0 }, true);
+
+        let mut ppm = Vec::new();
+        grid.write_ppm(&mut ppm).unwrap();
+        assert_eq!(ppm, b"P6\n2 1\n255\n\x00\x00\x00\xff\xff\xff".to_vec());
+
+        let mut png = Vec::new();
+        grid.write_png(&mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 1]);
+        assert_eq!(&png[png.len() - 12..], b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+    }
+}
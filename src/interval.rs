@@ -0,0 +1,229 @@
+#![allow(dead_code)]
+
+use std::ops::{Add, Range, Sub};
+
+// Set of values stored as sorted, disjoint half-open ranges. Overlapping and
+// touching ranges are merged on insertion, so [1, 3) and [3, 5) become [1, 5).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl <T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet{ ranges: Vec::new() }
+    }
+}
+
+impl <T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    // Index of the first range ending after `value`
+    fn first_ending_after(&self, value: T) -> usize {
+        self.ranges.partition_point(|range| range.end <= value)
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.start >= range.end {
+            return;
+        }
+        let first = self.ranges.partition_point(|other| other.end < range.start);
+        let last = self.ranges.partition_point(|other| other.start <= range.end);
+        let mut merged = range;
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.start >= range.end {
+            return;
+        }
+        let first = self.first_ending_after(range.start);
+        let last = self.ranges.partition_point(|other| other.start < range.end);
+        let mut remaining = Vec::new();
+        if first < last {
+            let (start, end) = (self.ranges[first].start, self.ranges[last - 1].end);
+            if start < range.start {
+                remaining.push(start..range.start);
+            }
+            if range.end < end {
+                remaining.push(range.end..end);
+            }
+        }
+        self.ranges.splice(first..last, remaining);
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = self.clone();
+        for range in other.ranges.iter() {
+            result.insert(range.clone());
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let (start, end) = (a.start.max(b.start), a.end.min(b.end));
+            if start < end {
+                result.ranges.push(start..end);
+            }
+            if a.end < b.end { i += 1 } else { j += 1 }
+        }
+        result
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = self.clone();
+        for range in other.ranges.iter() {
+            result.remove(range.clone());
+        }
+        result
+    }
+
+    // The parts of `within` not covered by the set, in ascending order
+    pub fn gaps(&self, within: Range<T>) -> Vec<Range<T>> {
+        let mut gaps = Vec::new();
+        let mut current = within.start;
+        for range in self.ranges[self.first_ending_after(within.start)..].iter() {
+            if range.start >= within.end {
+                break;
+            }
+            if current < range.start {
+                gaps.push(current..range.start);
+            }
+            current = range.end;
+        }
+        if current < within.end {
+            gaps.push(current..within.end);
+        }
+        gaps
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let index = self.first_ending_after(value);
+        index < self.ranges.len() && self.ranges[index].start <= value
+    }
+
+    // Whether all of `range` is in the set, empty ranges always are
+    pub fn contains_range(&self, range: &Range<T>) -> bool {
+        if range.start >= range.end {
+            return true;
+        }
+        let index = self.first_ending_after(range.start);
+        index < self.ranges.len() && self.ranges[index].start <= range.start && range.end <= self.ranges[index].end
+    }
+
+    // Whether any value of `range` is in the set
+    pub fn overlaps(&self, range: &Range<T>) -> bool {
+        if range.start >= range.end {
+            return false;
+        }
+        let index = self.first_ending_after(range.start);
+        index < self.ranges.len() && self.ranges[index].start < range.end
+    }
+}
+
+impl <T: Copy + Ord + Default + Add<Output = T> + Sub<Output = T>> IntervalSet<T> {
+    // Number of values in the set
+    pub fn total_length(&self) -> T {
+        self.ranges.iter().fold(T::default(), |total, range| total + (range.end - range.start))
+    }
+}
+
+// Whether all of `inner` is in `outer`, empty ranges always are
+pub fn range_contains<T: Ord>(outer: &Range<T>, inner: &Range<T>) -> bool {
+    inner.start >= inner.end || (outer.start <= inner.start && inner.end <= outer.end)
+}
+
+// Whether the two ranges have any value in common
+pub fn ranges_overlap<T: Ord>(a: &Range<T>, b: &Range<T>) -> bool {
+    a.start < a.end && b.start < b.end && a.start < b.end && b.start < a.end
+}
+
+impl <T: Copy + Ord> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl <T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(ranges: I) -> Self {
+        let mut set = IntervalSet::new();
+        for range in ranges {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut set: IntervalSet<i32> = [5..7, -2..3, 2..4, 10..10].into_iter().collect();
+        assert_eq!(set.ranges(), &[-2..4, 5..7]);
+        set.insert(4..5);
+        assert_eq!(set, IntervalSet::from(-2..7));
+        set.insert(9..12);
+        set.remove(0..2);
+        set.remove(6..10);
+        assert_eq!(set.ranges(), &[-2..0, 2..6, 10..12]);
+        assert_eq!(set.total_length(), 8);
+        set.remove(-5..20);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: IntervalSet<u32> = [0..5, 10..15].into_iter().collect();
+        let b: IntervalSet<u32> = [3..12, 14..20].into_iter().collect();
+        assert_eq!(a.union(&b), IntervalSet::from(0..20));
+        assert_eq!(a.intersection(&b).ranges(), &[3..5, 10..12, 14..15]);
+        assert_eq!(a.difference(&b).ranges(), &[0..3, 12..14]);
+        assert_eq!(b.difference(&a).ranges(), &[5..10, 15..20]);
+    }
+
+    #[test]
+    fn test_queries() {
+        let set: IntervalSet<i64> = [0..5, 10..15].into_iter().collect();
+        assert_eq!(set.gaps(-2..20), vec![-2..0, 5..10, 15..20]);
+        assert_eq!(set.gaps(2..12), vec![5..10]);
+        assert_eq!(set.gaps(1..4), vec![]);
+
+        assert!(set.contains(0) && set.contains(4) && !set.contains(5) && !set.contains(-1));
+        assert!(set.contains_range(&(10..15)));
+        assert!(!set.contains_range(&(4..11)));
+        assert!(set.overlaps(&(4..11)));
+        assert!(!set.overlaps(&(5..10)));
+    }
+
+    #[test]
+    fn test_range_helpers() {
+        assert!(range_contains(&(2..8), &(3..8)));
+        assert!(!range_contains(&(2..8), &(1..3)));
+        assert!(range_contains(&(2..8), &(9..9)));
+        assert!(ranges_overlap(&(2..8), &(7..10)));
+        assert!(!ranges_overlap(&(2..8), &(8..10)));
+        assert!(!ranges_overlap(&(2..8), &(4..4)));
+    }
+}
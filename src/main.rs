@@ -4,6 +4,12 @@
 mod grid;
 mod util;
 mod parser;
+mod grammar;
+mod image;
+mod interval;
+mod record;
+mod search;
+mod sensors;
 
 mod day1;
 mod day2;
@@ -1,6 +1,7 @@
 #[allow(dead_code)]
 use std::rc::Rc;
 use std::str::Chars;
+use std::io::BufRead;
 use core::fmt::Debug;
 
 #[derive(Debug)]
@@ -41,11 +42,154 @@ pub type ParseResult<'a> = Result<(Chars<'a>, Captured), ParseError>;
 
 pub trait Parser {
     fn parse<'a>(&self, input: &'a str, chars: Chars<'a>) -> ParseResult<'a>;
+
+    fn grammar(&self) -> Grammar;
 }
 
 impl Debug for dyn Parser {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{{dyn Parser}}")
+        write!(f, "{{dyn Parser {}}}", self.grammar())
+    }
+}
+
+// Description of what a parser accepts. Displays as a W3C style EBNF expression,
+// `crate::grammar` turns whole parsers into EBNF rules or railroad diagrams.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Grammar {
+    Char(char),
+    Range(char, char),
+    AlphaNum,
+    Lit(String),
+    Alt(Vec<Grammar>),
+    Seq(Vec<Grammar>),
+    Capture(Box<Grammar>),
+    Opt(Box<Grammar>),
+    Any(Box<Grammar>),
+    Many(Box<Grammar>),
+    List(Box<Grammar>, Box<Grammar>),
+    Rule(String, Box<Grammar>),
+}
+
+impl Grammar {
+    fn is_compound(&self) -> bool {
+        match self {
+            Grammar::Alt(_) | Grammar::Seq(_) | Grammar::List(_, _) => true,
+            Grammar::Capture(value) => value.is_compound(),
+            Grammar::Lit(text) => text.chars().count() > 1
+                && (text.chars().any(char::is_control) || (text.contains('"') && text.contains('\''))),
+            _ => false,
+        }
+    }
+
+    fn is_alt(&self) -> bool {
+        match self {
+            Grammar::Alt(_) => true,
+            Grammar::Capture(value) => value.is_alt(),
+            _ => false,
+        }
+    }
+
+    fn fmt_nested(&self, f: &mut std::fmt::Formatter<'_>, parenthesize: bool) -> std::fmt::Result {
+        if parenthesize {
+            write!(f, "( {} )", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+fn quote_terminal(text: &str) -> String {
+    if text.contains('"') {
+        format!("'{}'", text)
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+// Quoted literal, with control characters written as #xN between quoted runs
+fn fmt_terminal(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+    let mut pieces: Vec<String> = Vec::new();
+    let mut run = String::new();
+    for ch in text.chars() {
+        let quote_clash = (ch == '"' && run.contains('\'')) || (ch == '\'' && run.contains('"'));
+        if (ch.is_control() || quote_clash) && !run.is_empty() {
+            pieces.push(quote_terminal(&run));
+            run.clear();
+        }
+        if ch.is_control() {
+            pieces.push(format!("#x{:X}", ch as u32));
+        } else {
+            run.push(ch);
+        }
+    }
+    if !run.is_empty() || pieces.is_empty() {
+        pieces.push(quote_terminal(&run));
+    }
+    write!(f, "{}", pieces.join(" "))
+}
+
+fn fmt_class_char(f: &mut std::fmt::Formatter<'_>, ch: char) -> std::fmt::Result {
+    if ch.is_control() || ch == ']' || ch == '-' || ch == '^' || ch == '\\' {
+        write!(f, "#x{:X}", ch as u32)
+    } else {
+        write!(f, "{}", ch)
+    }
+}
+
+impl std::fmt::Display for Grammar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Grammar::Char(ch) => fmt_terminal(f, &ch.to_string()),
+            Grammar::Range(lower, upper) => {
+                write!(f, "[")?;
+                fmt_class_char(f, *lower)?;
+                write!(f, "-")?;
+                fmt_class_char(f, *upper)?;
+                write!(f, "]")
+            },
+            Grammar::AlphaNum => write!(f, "ALPHANUM"),
+            Grammar::Lit(text) => fmt_terminal(f, text),
+            Grammar::Alt(options) => {
+                for (idx, option) in options.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, " | ")?;
+                    }
+                    option.fmt_nested(f, option.is_alt())?;
+                }
+                Ok(())
+            },
+            Grammar::Seq(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, " ")?;
+                    }
+                    item.fmt_nested(f, item.is_alt())?;
+                }
+                Ok(())
+            },
+            Grammar::Capture(value) => write!(f, "{}", value),
+            Grammar::Opt(value) => {
+                value.fmt_nested(f, value.is_compound())?;
+                write!(f, "?")
+            },
+            Grammar::Any(value) => {
+                value.fmt_nested(f, value.is_compound())?;
+                write!(f, "*")
+            },
+            Grammar::Many(value) => {
+                value.fmt_nested(f, value.is_compound())?;
+                write!(f, "+")
+            },
+            Grammar::List(element, separator) => {
+                element.fmt_nested(f, element.is_alt())?;
+                write!(f, " ( ")?;
+                separator.fmt_nested(f, separator.is_alt())?;
+                write!(f, " ")?;
+                element.fmt_nested(f, element.is_alt())?;
+                write!(f, " )*")
+            },
+            Grammar::Rule(name, _) => write!(f, "{}", name),
+        }
     }
 }
 
@@ -63,6 +207,10 @@ impl Parser for Char {
             Some(_) => Ok((chars, Captured::None))
         }
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Char(self.ch)
+    }
 }
 
 #[derive(Debug)]
@@ -80,6 +228,10 @@ impl Parser for Range {
             Some(_) => Ok((chars, Captured::None))
         }
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Range(self.lower, self.upper)
+    }
 }
 
 #[derive(Debug)]
@@ -99,6 +251,17 @@ impl Parser for Alt {
             Ok(x) => Ok(x)
         }
     }
+
+    fn grammar(&self) -> Grammar {
+        let mut options = Vec::new();
+        for side in [&self.left, &self.right] {
+            match side.grammar() {
+                Grammar::Alt(nested) => options.extend(nested),
+                other => options.push(other),
+            }
+        }
+        Grammar::Alt(options)
+    }
 }
 
 #[derive(Debug)]
@@ -112,6 +275,10 @@ impl Parser for AlphaNum {
             Some(ch) => Err(ParseError { msg: format!("Expected alphanum character, but got '{}'", ch) })
         }
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::AlphaNum
+    }
 }
 
 #[derive(Debug)]
@@ -129,6 +296,10 @@ impl Parser for Lit {
         }
         Ok((chars, Captured::None))
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Lit(self.text.clone())
+    }
 }
 
 #[derive(Debug)]
@@ -153,6 +324,10 @@ impl Parser for Capture {
             }
         }
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Capture(Box::new(self.value.grammar()))
+    }
 }
 
 #[derive(Debug)]
@@ -169,6 +344,10 @@ impl Parser for Opt {
             ok => ok
         }
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Opt(Box::new(self.value.grammar()))
+    }
 }
 
 #[derive(Debug)]
@@ -199,6 +378,10 @@ impl Parser for Any {
 
         Ok((chars, flatten_captures_variable(many_captures)))
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Any(Box::new(self.value.grammar()))
+    }
 }
 
 #[derive(Debug)]
@@ -240,6 +423,10 @@ impl Parser for Many {
 
         Ok((chars, flatten_captures_variable(many_captures)))
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Many(Box::new(self.value.grammar()))
+    }
 }
 
 #[derive(Debug)]
@@ -264,6 +451,10 @@ impl Parser for Seq {
         }
         Ok((chars, flatten_captures_fixed(captures)))
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Seq(self.value.iter().map(|parser| parser.grammar()).collect())
+    }
 }
 
 #[derive(Debug)]
@@ -297,6 +488,28 @@ impl Parser for List {
         };
         Ok((chars, flatten_captures_variable(captures)))
     }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::List(Box::new(self.element.grammar()), Box::new(self.separator.grammar()))
+    }
+}
+
+// Gives a parser a name, so grammar exports show it as its own rule. Parsing is
+// unaffected.
+#[derive(Debug)]
+pub struct Named {
+    name: String,
+    value: Rc<dyn Parser>,
+}
+
+impl Parser for Named {
+    fn parse<'a>(&self, input: &'a str, chars: Chars<'a>) -> ParseResult<'a> {
+        self.value.parse(input, chars)
+    }
+
+    fn grammar(&self) -> Grammar {
+        Grammar::Rule(self.name.clone(), Box::new(self.value.grammar()))
+    }
 }
 
 pub fn make_char(ch: char) -> Rc<dyn Parser> {
@@ -304,7 +517,7 @@ pub fn make_char(ch: char) -> Rc<dyn Parser> {
 }
 
 pub fn make_range(lower: char, upper: char) -> Rc<dyn Parser> {
-    assert!(lower < upper);
+    assert!(lower <= upper, "Empty char range '{}'..='{}'", lower, upper);
     Rc::new(Range{ lower, upper })
 }
 
@@ -364,6 +577,10 @@ pub fn make_list(element: Rc<dyn Parser>, separator: Rc<dyn Parser>) -> Rc<dyn P
     Rc::new(List{ element, separator })
 }
 
+pub fn make_named(name: &str, value: Rc<dyn Parser>) -> Rc<dyn Parser> {
+    Rc::new(Named{ name: name.to_owned(), value })
+}
+
 fn flatten_captures_variable(maybe_captures: Vec<Captured>) -> Captured {
     if maybe_captures.len() > 0 {
         Captured::Many(maybe_captures)
@@ -390,7 +607,10 @@ pub fn parse_wildcard<'a>(pattern: &str, wildcard: char, text: &'a str) -> Optio
         if expected == wildcard {
             let pc_copy = pc.clone();
             let next_expected = pc.next();
-            assert!(next_expected != Some(wildcard));
+            if next_expected == Some(wildcard) {
+                // Adjacent wildcards are ambiguous
+                return None
+            }
             let capture_start = text.len() - tc.as_str().len();
             loop {
                 let tc_copy = tc.clone();
@@ -415,6 +635,116 @@ pub fn parse_wildcard<'a>(pattern: &str, wildcard: char, text: &'a str) -> Optio
     Some(captures)
 }
 
+// Parses consecutive records from a buffered reader, pulling in more lines only
+// when the buffered text is not enough to decide where the current record ends.
+// A record counts as complete once it parsed successfully and did not consume
+// everything that is buffered (or the reader is exhausted), since a parser that
+// stops exactly at the end of the buffer might still want to consume more.
+// Whitespace left over at the end of the input is ignored. A record that fails
+// to parse ends at the next blank line, or after MAX_RECORD_LINES lines, and
+// parsing picks up again after it.
+pub struct Records<R: BufRead> {
+    reader: R,
+    parser: Rc<dyn Parser>,
+    buffer: String,
+    eof: bool,
+    // Lines taken out of the buffer so far, i.e. the 0-based line it starts on
+    line: usize,
+    buffered_lines: usize,
+    last_line_blank: bool,
+}
+
+// Lines a failing record may span before giving up on it
+const MAX_RECORD_LINES: usize = 32;
+
+impl <R: BufRead> Records<R> {
+    fn fill(&mut self) -> Result<(), ParseError> {
+        let start = self.buffer.len();
+        match self.reader.read_line(&mut self.buffer) {
+            Ok(0) => {
+                self.eof = true;
+                Ok(())
+            },
+            Ok(_) => {
+                self.buffered_lines += 1;
+                self.last_line_blank = self.buffer[start..].trim().is_empty();
+                Ok(())
+            },
+            Err(error) => {
+                self.eof = true;
+                Err(ParseError{ msg: format!("Failed to read input: {}", error) })
+            }
+        }
+    }
+
+    fn take(&mut self, consumed: usize) {
+        let lines = self.buffer[..consumed].matches('\n').count();
+        self.buffer.drain(..consumed);
+        self.line += lines;
+        self.buffered_lines -= lines;
+    }
+
+    // A failing record gets no more lines once it is closed by a blank line
+    // or has grown too long
+    fn at_record_end(&self) -> bool {
+        self.eof
+            || (self.last_line_blank && !self.buffer.trim().is_empty())
+            || self.buffered_lines >= MAX_RECORD_LINES
+    }
+}
+
+impl <R: BufRead> Iterator for Records<R> {
+    type Item = Result<Captured, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.eof && self.buffer.trim().is_empty() {
+                return None;
+            }
+            if !self.buffer.is_empty() {
+                let input = self.buffer.as_str();
+                // 1-based line the record starts on, after any blank lines
+                let skipped = &input[..input.len() - input.trim_start().len()];
+                let line = self.line + 1 + skipped.matches('\n').count();
+                let located = |err: ParseError| ParseError{ msg: format!("Record at line {}: {}", line, err.msg) };
+                let consumed = match self.parser.parse(input, input.chars()) {
+                    Ok((rest, captured)) => {
+                        let consumed = input.len() - rest.as_str().len();
+                        if consumed > 0 && (consumed < input.len() || self.eof) {
+                            Some((consumed, Ok(captured)))
+                        } else if self.eof {
+                            Some((input.len(), Err(located(ParseError{ msg: "Record parser made no progress".to_owned() }))))
+                        } else {
+                            None
+                        }
+                    },
+                    Err(err) if self.at_record_end() => Some((input.len(), Err(located(err)))),
+                    Err(_) => None,
+                };
+                if let Some((consumed, result)) = consumed {
+                    self.take(consumed);
+                    return Some(result);
+                }
+            }
+            if let Err(err) = self.fill() {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+pub fn parse_records<R: BufRead>(reader: R, parser: Rc<dyn Parser>) -> Records<R> {
+    Records{
+        reader,
+        parser,
+        buffer: String::new(),
+        eof: false,
+        line: 0,
+        buffered_lines: 0,
+        last_line_blank: false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -562,6 +892,168 @@ mod tests {
             parse_wildcard("asdf x=*, y=*", '*', "sdf x=-123, y=321"),
             None
         );
+
+        assert_eq!(parse_wildcard("x=**", '*', "x=12"), None);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_parse_records() {
+        let record = make_seq(vec![
+            make_lit("item "), make_capture(make_number()), make_char(':'),
+            make_any(make_ws()),
+        ]);
+        let input = "item 1:\nitem -23:\n\nitem 456:\n\n";
+        let records: Vec<Captured> = parse_records(input.as_bytes(), record.clone())
+            .map(|record| record.unwrap()).collect();
+        assert_eq!(records, vec![Captured::from("1"), Captured::from("-23"), Captured::from("456")]);
+
+        let mut records = parse_records("item 1:\nitem x:\n".as_bytes(), record.clone());
+        assert_matches!(records.next(), Some(Ok(Captured::One(value))) if value == "1");
+        assert_matches!(records.next(), Some(Err(_)));
+        assert_matches!(records.next(), None);
+
+        // A broken record ends at the next blank line
+        let mut records = parse_records("item 1:\nitem x:\nmore\n\nitem 3:\n".as_bytes(), record.clone());
+        assert_matches!(records.next(), Some(Ok(_)));
+        assert_matches!(records.next(), Some(Err(ParseError{ msg })) if msg.starts_with("Record at line 2:"));
+        assert_matches!(records.next(), Some(Ok(Captured::One(value))) if value == "3");
+        assert_matches!(records.next(), None);
+
+        // or after MAX_RECORD_LINES lines without one
+        let input = "x\n".repeat(MAX_RECORD_LINES + 8);
+        let errors: Vec<String> = parse_records(input.as_bytes(), record)
+            .map(|record| record.unwrap_err().msg).collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[1].starts_with(&format!("Record at line {}:", MAX_RECORD_LINES + 1)));
+    }
+}
+
+#[cfg(test)]
+mod properties {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse_text<'a>(parser: &Rc<dyn Parser>, text: &'a str) -> ParseResult<'a> {
+        parser.parse(text, text.chars())
+    }
+
+    // Nested lists of words, e.g. "[ab,[c,d1],e]". Lists are never empty since
+    // `List` needs at least one element.
+    fn captured_tree() -> impl Strategy<Value = Captured> {
+        let leaf = "[a-z0-9]{1,5}".prop_map(Captured::One);
+        leaf.prop_recursive(3, 32, 4, |inner| {
+            prop::collection::vec(inner, 1..4).prop_map(Captured::Many)
+        })
+    }
+
+    fn print_tree(tree: &Captured) -> String {
+        match tree {
+            Captured::None => String::new(),
+            Captured::One(word) => word.clone(),
+            Captured::Many(items) => {
+                let items: Vec<String> = items.iter().map(print_tree).collect();
+                format!("[{}]", items.join(","))
+            }
+        }
+    }
+
+    fn make_tree_parser(depth: usize) -> Rc<dyn Parser> {
+        let word = make_capture(make_word());
+        if depth == 0 {
+            return word;
+        }
+        make_alt(
+            word,
+            make_seq(vec![
+                make_char('['),
+                make_list(make_tree_parser(depth - 1), make_char(',')),
+                make_char(']'),
+            ])
+        )
+    }
+
+    // Parsers used by the days, run against arbitrary text to check they fail
+    // gracefully instead of panicking.
+    fn sample_parsers() -> Vec<Rc<dyn Parser>> {
+        vec![
+            make_number(),
+            make_word(),
+            make_ws(),
+            make_capture(make_list(make_capture(make_number()), make_lit(", "))),
+            make_many(make_seq(vec![make_capture(make_number()), make_lit(" -> ")])),
+            make_any(make_alt(make_range('a', 'a'), make_capture(make_range('0', '9')))),
+            make_opt(make_capture(make_lit("ü"))),
+            make_tree_parser(3),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn tree_round_trip(tree in captured_tree()) {
+            let text = print_tree(&tree);
+            let result = parse_text(&make_tree_parser(3), &text);
+            prop_assert!(result.is_ok(), "failed to parse {:?}", text);
+            let (rest, captured) = result.unwrap();
+            prop_assert_eq!(rest.as_str(), "");
+            prop_assert_eq!(captured, tree);
+        }
+
+        #[test]
+        fn list_does_not_consume_trailing_separator(
+            words in prop::collection::vec("[a-z0-9]{1,4}", 1..6),
+            suffix in "[ ,!a-z]{0,4}",
+        ) {
+            let parser = make_list(make_capture(make_word()), make_lit(", "));
+            let text = format!("{}{}", words.join(", "), suffix);
+            if let Ok((rest, _)) = parse_text(&parser, &text) {
+                let consumed = &text[.. text.len() - rest.as_str().len()];
+                prop_assert!(!consumed.ends_with(", "), "consumed {:?} of {:?}", consumed, text);
+            }
+        }
+
+        #[test]
+        fn number_captures_round_trip(number in any::<i64>(), suffix in "[^0-9]{0,3}") {
+            let text = format!("{}{}", number, suffix);
+            let (rest, captured) = parse_text(&make_capture(make_number()), &text).unwrap();
+            prop_assert_eq!(rest.as_str(), suffix.as_str());
+            prop_assert_eq!(captured.as_one().parse::<i64>().unwrap(), number);
+        }
+
+        #[test]
+        fn parsers_do_not_panic(text in "\\PC{0,24}") {
+            for parser in sample_parsers() {
+                if let Ok((rest, _)) = parse_text(&parser, &text) {
+                    prop_assert!(text.ends_with(rest.as_str()));
+                }
+            }
+        }
+
+        #[test]
+        fn range_accepts_its_bounds(lower in any::<char>(), upper in any::<char>()) {
+            let (lower, upper) = if lower <= upper { (lower, upper) } else { (upper, lower) };
+            let parser = make_range(lower, upper);
+            prop_assert!(parse_text(&parser, &lower.to_string()).is_ok());
+            prop_assert!(parse_text(&parser, &upper.to_string()).is_ok());
+        }
+
+        #[test]
+        fn wildcard_round_trip(
+            values in prop::collection::vec("[^,*]{0,6}", 1..4),
+            separator in "[a-z=]{0,2}",
+        ) {
+            let separator = format!(",{}", separator);
+            let pattern = vec!["*"; values.len()].join(&separator);
+            let text = values.join(&separator);
+            let captures = parse_wildcard(&pattern, '*', &text);
+            prop_assert_eq!(captures, Some(values.iter().map(|x| x.as_str()).collect()));
+        }
+
+        #[test]
+        fn wildcard_does_not_panic(pattern in "[a*,]{0,8}", text in "\\PC{0,16}") {
+            let captures = parse_wildcard(&pattern, '*', &text);
+            if pattern.contains("**") {
+                prop_assert_eq!(captures, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+#![allow(dead_code)]
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::grid::{Grid, InfiniteGrid, Point, RenderOptions};
+use crate::image::{GifWriter, ToColor};
+use crate::util::option_value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    // asciinema v2: a JSON header line followed by one `[time, "o", text]`
+    // event per frame, each clearing the screen first
+    Cast,
+    Gif,
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Option<Format> {
+        if path.ends_with(".cast") {
+            Some(Format::Cast)
+        } else if path.ends_with(".gif") {
+            Some(Format::Gif)
+        } else {
+            None
+        }
+    }
+}
+
+enum Encoder<W: Write> {
+    Cast(W),
+    Gif(GifWriter<W>),
+}
+
+// Writes snapshots of a grid simulation as an asciinema cast or an animated
+// GIF while it runs. Snapshots may differ in size and position, they are drawn
+// onto a fixed canvas given by `bounds`, or else covering the first snapshot.
+// Anything outside of the canvas is cut off.
+pub struct Recorder<T: Copy, W: Write = BufWriter<File>> {
+    name: String,
+    format: Format,
+    // Until the first frame fixes the canvas size
+    out: Option<W>,
+    encoder: Option<Encoder<W>>,
+    background: T,
+    bounds: Option<(Point, Point)>,
+    frames: usize,
+    steps: usize,
+    last_kept: bool,
+    every: usize,
+    delay_ms: u32,
+    scale: u32,
+    y_up: bool,
+}
+
+impl <T: Copy> Recorder<T> {
+    // Recorder writing to `path`, in the format its extension tells
+    pub fn new(path: &str, background: T) -> Recorder<T> {
+        let format = Format::from_path(path).unwrap_or_else(|| panic!("Unknown recording format: {}", path));
+        let file = File::create(path).unwrap_or_else(|error| panic!("Unable to write {}: {}", path, error));
+        Recorder::to_writer(BufWriter::new(file), format, background).named(path)
+    }
+
+    // Recorder for `--record <path>`, keeping every n-th step with
+    // `--record-every <n>`
+    pub fn from_args(background: T) -> Option<Recorder<T>> {
+        let path = option_value("record")?;
+        let every = option_value("record-every")
+            .map(|every| every.parse::<usize>().unwrap_or_else(|error| panic!("--record-every: {}", error)))
+            .unwrap_or(1);
+        Some(Recorder::new(&path, background).every(every))
+    }
+}
+
+impl <T: Copy, W: Write> Recorder<T, W> {
+    pub fn to_writer(out: W, format: Format, background: T) -> Recorder<T, W> {
+        Recorder{
+            name: String::from("recording"),
+            format,
+            out: Some(out),
+            encoder: None,
+            background,
+            bounds: None,
+            frames: 0,
+            steps: 0,
+            last_kept: false,
+            every: 1,
+            delay_ms: 100,
+            scale: 4,
+            y_up: false,
+        }
+    }
+
+    // Name used in messages, the path for file recordings
+    fn named(mut self, name: &str) -> Recorder<T, W> {
+        self.name = name.to_owned();
+        self
+    }
+
+    pub fn every(mut self, every: usize) -> Recorder<T, W> {
+        assert!(every > 0, "Recording every 0th step");
+        self.every = every;
+        self
+    }
+
+    pub fn delay_ms(mut self, delay_ms: u32) -> Recorder<T, W> {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    // Pixels per cell in each direction for GIF output
+    pub fn scale(mut self, scale: u32) -> Recorder<T, W> {
+        assert!(scale > 0, "Scaling GIF frames by 0");
+        self.scale = scale;
+        self
+    }
+
+    // Draw larger y coordinates further up
+    pub fn y_up(mut self) -> Recorder<T, W> {
+        self.y_up = true;
+        self
+    }
+
+    // Canvas between two corners (both inclusive)
+    pub fn bounds(mut self, min: Point, max: Point) -> Recorder<T, W> {
+        assert!(min.x <= max.x && min.y <= max.y, "Empty recording bounds {} to {}", min, max);
+        self.bounds = Some((min, max));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames
+    }
+
+    // Counts a step and tells whether it is one of the kept ones
+    fn next_step(&mut self) -> bool {
+        self.steps += 1;
+        self.last_kept = (self.steps - 1).is_multiple_of(self.every);
+        self.last_kept
+    }
+
+    // `grid` placed at `origin` on the canvas, with rows in the order they are
+    // drawn
+    fn canvas(&mut self, grid: &Grid<T>, origin: Point) -> Grid<T> {
+        let (min, max) = *self.bounds.get_or_insert((origin, Point{
+            x: origin.x + grid.width() - 1,
+            y: origin.y + grid.height() - 1,
+        }));
+        let mut canvas = Grid::new(max.x - min.x + 1, max.y - min.y + 1, self.background);
+        for p in canvas.points() {
+            let y = if self.y_up { max.y - p.y } else { min.y + p.y };
+            let source = Point{ x: min.x + p.x - origin.x, y: y - origin.y };
+            canvas.set(&p, grid.get_or_default(&source, self.background));
+        }
+        canvas
+    }
+}
+
+impl <T: Copy + Display + ToColor, W: Write> Recorder<T, W> {
+    pub fn capture(&mut self, grid: &Grid<T>) {
+        self.capture_at(grid, Point{ x: 0, y: 0 });
+    }
+
+    // Records a step where the (0, 0) corner of `grid` is at `origin`
+    pub fn capture_at(&mut self, grid: &Grid<T>, origin: Point) {
+        if self.next_step() {
+            self.write_frame(grid, origin);
+        }
+    }
+
+    pub fn capture_infinite(&mut self, grid: &InfiniteGrid<T>) {
+        if self.next_step() {
+            if let Some((grid, origin)) = grid.to_grid() {
+                self.write_frame(&grid, origin);
+            }
+        }
+    }
+
+    // Records the final state, unless the last step was kept already
+    pub fn capture_last_at(&mut self, grid: &Grid<T>, origin: Point) {
+        if !self.last_kept {
+            self.write_frame(grid, origin);
+            self.last_kept = true;
+        }
+    }
+
+    pub fn capture_last_infinite(&mut self, grid: &InfiniteGrid<T>) {
+        if !self.last_kept {
+            if let Some((grid, origin)) = grid.to_grid() {
+                self.write_frame(&grid, origin);
+            }
+            self.last_kept = true;
+        }
+    }
+
+    fn write_frame(&mut self, grid: &Grid<T>, origin: Point) {
+        let canvas = self.canvas(grid, origin);
+        self.encode(&canvas).unwrap_or_else(|error| panic!("Unable to write {}: {}", self.name, error));
+        self.frames += 1;
+    }
+
+    fn encode(&mut self, canvas: &Grid<T>) -> io::Result<()> {
+        let scale = self.scale as i64;
+        if self.encoder.is_none() {
+            let out = self.out.take().unwrap();
+            self.encoder = Some(match self.format {
+                Format::Cast => {
+                    let mut out = out;
+                    writeln!(out, "{{\"version\": 2, \"width\": {}, \"height\": {}}}", canvas.width(), canvas.height())?;
+                    Encoder::Cast(out)
+                },
+                Format::Gif => {
+                    let (width, height) = (canvas.width() * scale, canvas.height() * scale);
+                    if width > u16::MAX as i64 || height > u16::MAX as i64 {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                            format!("Frames of {}x{} pixels are too large for a GIF", width, height)));
+                    }
+                    Encoder::Gif(GifWriter::new(out, width as u16, height as u16, (self.delay_ms / 10) as u16)?)
+                },
+            });
+        }
+        match self.encoder.as_mut().unwrap() {
+            Encoder::Cast(out) => {
+                let mut text = String::from("\x1b[H\x1b[2J");
+                let mut frame = String::new();
+                canvas.render(&mut frame, &RenderOptions::default()).unwrap();
+                text.push_str(&frame.replace('\n', "\r\n"));
+                let time = self.frames as f64 * self.delay_ms as f64 / 1000.0;
+                writeln!(out, "[{:.3}, \"o\", {}]", time, json_string(&text))
+            },
+            Encoder::Gif(gif) => {
+                let pixels: Vec<_> = (0..canvas.height() * scale)
+                    .flat_map(|y| (0..canvas.width() * scale).map(move |x| Point{ x: x / scale, y: y / scale }))
+                    .map(|p| canvas.get(&p).to_color())
+                    .collect();
+                gif.frame(&pixels)
+            },
+        }
+    }
+
+    // Completes the recording, returning the writer it went to
+    pub fn finish_writer(self) -> io::Result<W> {
+        match self.encoder {
+            Some(Encoder::Cast(mut out)) => {
+                out.flush()?;
+                Ok(out)
+            },
+            Some(Encoder::Gif(gif)) => gif.finish(),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "Nothing was recorded")),
+        }
+    }
+
+    pub fn finish(self) {
+        let (name, frames) = (self.name.clone(), self.frames);
+        self.finish_writer().unwrap_or_else(|error| panic!("Unable to write {}: {}", name, error));
+        println!("Wrote {} frames to {}", frames, name);
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut result = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            ch if (ch as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_cast() {
+        let mut recorder = Recorder::to_writer(Vec::new(), Format::Cast, '.')
+            .every(2)
+            .bounds(Point{ x: 0, y: 0 }, Point{ x: 3, y: 0 });
+        let mut grid = Grid::new(2, 1, '.');
+        for (step, x) in [0, 1, 0].iter().enumerate() {
+            grid.set(&Point{ x: *x, y: 0 }, char::from(b'a' + step as u8));
+            recorder.capture_at(&grid, Point{ x: step as i64, y: 0 });
+        }
+        assert_eq!(recorder.frame_count(), 2);
+        recorder.capture_last_at(&grid, Point{ x: 2, y: 0 });
+        assert_eq!(recorder.frame_count(), 2);
+
+        let cast = recorder.finish_writer().unwrap();
+        assert_eq!(String::from_utf8(cast).unwrap(), concat!(
+            "{\"version\": 2, \"width\": 4, \"height\": 1}\n",
+            "[0.000, \"o\", \"\\u001b[H\\u001b[2Ja...\\r\\n\"]\n",
+            "[0.100, \"o\", \"\\u001b[H\\u001b[2J..cb\\r\\n\"]\n",
+        ));
+    }
+
+    #[test]
+    fn test_canvas_y_up() {
+        // Without bounds, the first frame sets the canvas and later ones are cut
+        let mut recorder = Recorder::to_writer(Vec::new(), Format::Cast, '.').y_up();
+        let mut grid = Grid::new(1, 2, '.');
+        grid.set(&Point{ x: 0, y: 0 }, '#');
+        recorder.capture_at(&grid, Point{ x: 0, y: 0 });
+        recorder.capture_at(&grid, Point{ x: 0, y: 1 });
+        let cast = String::from_utf8(recorder.finish_writer().unwrap()).unwrap();
+        assert!(cast.starts_with("{\"version\": 2, \"width\": 1, \"height\": 2}\n"));
+        assert!(cast.contains("2J#\\r\\n.\\r\\n\""));
+        assert!(cast.contains("2J.\\r\\n#\\r\\n\""));
+
+        let mut recorder = Recorder::to_writer(Vec::new(), Format::Gif, false)
+            .bounds(Point{ x: 0, y: 0 }, Point{ x: 0, y: 2 });
+        recorder.capture(&Grid::new(1, 2, true));
+        let gif = recorder.finish_writer().unwrap();
+        assert_eq!(&gif[6..10], &[4, 0, 12, 0]);
+    }
+}
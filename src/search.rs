@@ -0,0 +1,313 @@
+#![allow(dead_code)]
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::grid::{Connectivity, Grid, Point};
+
+// The plain walk lives with the grids, which fill regions with it
+pub use crate::grid::breadth_first;
+
+// Shortest path searches over graphs given as a successor function, so nodes
+// can be grid points, states of a simulation or anything else hashable.
+
+// Nodes from the start to the goal (both inclusive) and the summed step costs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N, C> {
+    pub nodes: Vec<N>,
+    pub cost: C,
+}
+
+impl <N, C> Path<N, C> {
+    pub fn start(&self) -> &N {
+        self.nodes.first().unwrap()
+    }
+
+    pub fn goal(&self) -> &N {
+        self.nodes.last().unwrap()
+    }
+
+    // Number of steps, one less than the number of nodes
+    pub fn steps(&self) -> usize {
+        self.nodes.len() - 1
+    }
+}
+
+// Step costs, default being zero
+pub trait Cost: Copy + Ord + Default + Add<Output = Self> {}
+
+impl <T: Copy + Ord + Default + Add<Output = T>> Cost for T {}
+
+// Nodes seen so far with the best known cost and the node it was reached from
+struct Visited<N, C> {
+    indices: HashMap<N, usize>,
+    nodes: Vec<(N, C, Option<usize>)>,
+}
+
+impl <N: Clone + Eq + Hash, C: Copy> Visited<N, C> {
+    fn new() -> Visited<N, C> {
+        Visited{ indices: HashMap::new(), nodes: Vec::new() }
+    }
+
+    fn insert(&mut self, node: N, cost: C, parent: Option<usize>) -> usize {
+        let index = self.nodes.len();
+        self.indices.insert(node.clone(), index);
+        self.nodes.push((node, cost, parent));
+        index
+    }
+
+    fn path_to(&self, index: usize) -> Path<N, C> {
+        let cost = self.nodes[index].1;
+        let mut nodes = Vec::new();
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let (node, _, parent) = &self.nodes[index];
+            nodes.push(node.clone());
+            current = *parent;
+        }
+        nodes.reverse();
+        Path{ nodes, cost }
+    }
+}
+
+// Cheapest path from `start` to the first node popped that satisfies
+// `is_goal`. `heuristic` must never overestimate the remaining cost.
+pub fn a_star<N, C, SuccessorsFn, Successors, HeuristicFn, GoalFn>(start: N, mut successors: SuccessorsFn,
+    mut heuristic: HeuristicFn, mut is_goal: GoalFn) -> Option<Path<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    SuccessorsFn: FnMut(&N) -> Successors,
+    Successors: IntoIterator<Item = (N, C)>,
+    HeuristicFn: FnMut(&N) -> C,
+    GoalFn: FnMut(&N) -> bool,
+{
+    let mut visited = Visited::new();
+    let mut frontier = BinaryHeap::new();
+
+    let estimate = heuristic(&start);
+    let start_index = visited.insert(start, C::default(), None);
+    frontier.push(Reverse((estimate, C::default(), start_index)));
+
+    while let Some(Reverse((_, cost, index))) = frontier.pop() {
+        let (node, best_cost, _) = visited.nodes[index].clone();
+        if cost > best_cost {
+            // superseded by a cheaper entry
+            continue;
+        }
+        if is_goal(&node) {
+            return Some(visited.path_to(index));
+        }
+        for (next, step_cost) in successors(&node) {
+            let next_cost = cost + step_cost;
+            let improved = match visited.indices.get(&next) {
+                Some(&next_index) if visited.nodes[next_index].1 <= next_cost => None,
+                Some(&next_index) => {
+                    visited.nodes[next_index].1 = next_cost;
+                    visited.nodes[next_index].2 = Some(index);
+                    Some(next_index)
+                },
+                None => Some(visited.insert(next, next_cost, Some(index))),
+            };
+            if let Some(next_index) = improved {
+                let estimate = heuristic(&visited.nodes[next_index].0);
+                frontier.push(Reverse((next_cost + estimate, next_cost, next_index)));
+            }
+        }
+    }
+    None
+}
+
+pub fn dijkstra<N, C, SuccessorsFn, Successors, GoalFn>(start: N, successors: SuccessorsFn,
+    is_goal: GoalFn) -> Option<Path<N, C>>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    SuccessorsFn: FnMut(&N) -> Successors,
+    Successors: IntoIterator<Item = (N, C)>,
+    GoalFn: FnMut(&N) -> bool,
+{
+    a_star(start, successors, |_| C::default(), is_goal)
+}
+
+// Path with the fewest steps when every step costs the same
+pub fn bfs<N, SuccessorsFn, Successors, GoalFn>(start: N, mut successors: SuccessorsFn,
+    mut is_goal: GoalFn) -> Option<Path<N, usize>>
+where
+    N: Clone + Eq + Hash,
+    SuccessorsFn: FnMut(&N) -> Successors,
+    Successors: IntoIterator<Item = N>,
+    GoalFn: FnMut(&N) -> bool,
+{
+    let mut visited = Visited::new();
+    let start_goal = is_goal(&start);
+    let start_index = visited.insert(start, 0, None);
+    if start_goal {
+        return Some(visited.path_to(start_index));
+    }
+
+    let mut queue = VecDeque::from([start_index]);
+    while let Some(index) = queue.pop_front() {
+        let (node, steps, _) = visited.nodes[index].clone();
+        for next in successors(&node) {
+            if visited.indices.contains_key(&next) {
+                continue;
+            }
+            let next_goal = is_goal(&next);
+            let next_index = visited.insert(next, steps + 1, Some(index));
+            if next_goal {
+                return Some(visited.path_to(next_index));
+            }
+            queue.push_back(next_index);
+        }
+    }
+    None
+}
+
+// Fewest steps from the nearest of `sources` to every cell of `grid`, None
+// where no source can reach. `can_step(from, to)` tells whether moving between
+// two adjacent cells is allowed.
+pub fn distance_map<T, Sources, StepFn>(grid: &Grid<T>, sources: Sources, connectivity: Connectivity,
+    mut can_step: StepFn) -> Grid<Option<usize>>
+where
+    T: Copy,
+    Sources: IntoIterator<Item = Point>,
+    StepFn: FnMut(&Point, &Point) -> bool,
+{
+    let mut distances = Grid::new(grid.width(), grid.height(), None);
+    let steps = |from: &Point| grid.stencil(from, connectivity.offsets())
+        .filter(|to| can_step(from, to))
+        .collect::<Vec<_>>();
+    breadth_first(sources, steps, |p, steps| {
+        let new = distances.get(p).is_none();
+        if new {
+            distances.set(p, Some(steps));
+        }
+        new
+    });
+    distances
+}
+
+// Cheapest cost from the nearest of `sources` to every cell of `grid`, None
+// where no source can reach. `step_cost(from, to)` is the cost of moving
+// between two adjacent cells, None if that is not allowed.
+pub fn cost_map<T, C, Sources, CostFn>(grid: &Grid<T>, sources: Sources, connectivity: Connectivity,
+    mut step_cost: CostFn) -> Grid<Option<C>>
+where
+    T: Copy,
+    C: Cost,
+    Sources: IntoIterator<Item = Point>,
+    CostFn: FnMut(&Point, &Point) -> Option<C>,
+{
+    let mut costs: Grid<Option<C>> = Grid::new(grid.width(), grid.height(), None);
+    let mut frontier = BinaryHeap::new();
+    for source in sources {
+        costs.set(&source, Some(C::default()));
+        frontier.push(Reverse((C::default(), source)));
+    }
+    while let Some(Reverse((cost, p))) = frontier.pop() {
+        if costs.get(&p).is_some_and(|best| cost > best) {
+            // superseded by a cheaper entry
+            continue;
+        }
+        for next in grid.stencil(&p, connectivity.offsets()) {
+            let Some(step) = step_cost(&p, &next) else { continue };
+            let next_cost = cost + step;
+            if costs.get(&next).is_none_or(|best| next_cost < best) {
+                costs.set(&next, Some(next_cost));
+                frontier.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+    costs
+}
+
+// The reachable cell farthest from the sources of a distance or cost map, the
+// first in row-major order on ties
+pub fn farthest<C: Copy + Ord>(map: &Grid<Option<C>>) -> Option<(Point, C)> {
+    map.points()
+        .filter_map(|p| map.get(&p).map(|distance| (p, distance)))
+        .fold(None, |farthest, (p, distance)| match farthest {
+            Some((_, max)) if max >= distance => farthest,
+            _ => Some((p, distance)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a -1-> b -1-> c -1-> d, with a costly shortcut a -5-> d and a dead end e
+    fn successors(node: &char) -> Vec<(char, u32)> {
+        match node {
+            'a' => vec![('b', 1), ('d', 5), ('e', 1)],
+            'b' => vec![('c', 1)],
+            'c' => vec![('d', 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra('a', successors, |node| *node == 'd').unwrap();
+        assert_eq!(path, Path{ nodes: vec!['a', 'b', 'c', 'd'], cost: 3 });
+        assert_eq!(path.steps(), 3);
+        assert_eq!(dijkstra('a', successors, |node| *node == 'a').unwrap().nodes, vec!['a']);
+        assert_eq!(dijkstra('b', successors, |node| *node == 'e'), None);
+    }
+
+    #[test]
+    fn test_bfs() {
+        let unweighted = |node: &char| successors(node).into_iter().map(|(next, _)| next);
+        let path = bfs('a', unweighted, |node| *node == 'd').unwrap();
+        assert_eq!(path, Path{ nodes: vec!['a', 'd'], cost: 1 });
+        assert_eq!(bfs('c', unweighted, |node| *node == 'a'), None);
+    }
+
+    #[test]
+    fn test_a_star_on_grid() {
+        // Open 10x10 area with a wall at x = 5 that has a gap at y = 9
+        let blocked = |(x, y): (i32, i32)| !(0..10).contains(&x) || !(0..10).contains(&y) || (x == 5 && y != 9);
+        let neighbors = |&(x, y): &(i32, i32)| {
+            [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter()
+                .filter(move |next| !blocked(*next))
+                .map(|next| (next, 1))
+        };
+        let goal = (9, 0);
+        let manhattan = |&(x, y): &(i32, i32)| ((goal.0 - x).abs() + (goal.1 - y).abs()) as u32;
+
+        let path = a_star((0, 0), neighbors, manhattan, |node| *node == goal).unwrap();
+        assert_eq!(path.cost, 27);
+        assert_eq!(path.nodes.len(), 28);
+        assert!(path.nodes.contains(&(5, 9)));
+        assert_eq!(dijkstra((0, 0), neighbors, |node| *node == goal).unwrap().cost, 27);
+    }
+
+    #[test]
+    fn test_distance_maps() {
+        let grid: Grid<char> = Grid::from_str_with("a.#.\n..#.\na...", |ch, _| Ok(ch)).unwrap();
+        let open = |_: &Point, to: &Point| grid.get(to) != '#';
+        let sources = grid.points().filter(|p| grid.get(p) == 'a');
+
+        let distances = distance_map(&grid, sources, Connectivity::Four, open);
+        assert_eq!(distances.get(&Point{ x: 1, y: 1 }), Some(2));
+        assert_eq!(distances.get(&Point{ x: 3, y: 0 }), Some(5));
+        assert_eq!(distances.get(&Point{ x: 2, y: 0 }), None);
+        assert_eq!(farthest(&distances), Some((Point{ x: 3, y: 0 }, 5)));
+
+        let diagonal = distance_map(&grid, [Point{ x: 0, y: 0 }], Connectivity::Eight, open);
+        assert_eq!(diagonal.get(&Point{ x: 3, y: 0 }), Some(4));
+
+        // Stepping onto a '.' costs 2, onto an 'a' 1
+        let costs = cost_map(&grid, [Point{ x: 0, y: 0 }], Connectivity::Four, |_, to| match grid.get(to) {
+            '#' => None,
+            'a' => Some(1),
+            _ => Some(2),
+        });
+        assert_eq!(costs.get(&Point{ x: 0, y: 2 }), Some(3));
+        assert_eq!(costs.get(&Point{ x: 1, y: 2 }), Some(5));
+        assert_eq!(farthest(&costs), Some((Point{ x: 3, y: 0 }, 13)));
+    }
+}
@@ -0,0 +1,283 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::grid::{ManhattanBall, Point};
+use crate::interval::IntervalSet;
+use crate::parser::parse_wildcard;
+
+// Beacon sensors that only report their closest beacon, and what that tells
+// about where other beacons cannot be.
+
+// A sensor and the closest beacon to it, no other beacon is as close
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sensor {
+    pub position: Point,
+    pub beacon: Point,
+}
+
+impl Sensor {
+    pub fn radius(&self) -> i64 {
+        (self.beacon - self.position).l1_norm()
+    }
+
+    // The cells that cannot hold a beacon other than the closest one
+    pub fn coverage(&self) -> ManhattanBall {
+        ManhattanBall::new(self.position, self.radius())
+    }
+}
+
+impl FromStr for Sensor {
+    type Err = String;
+
+    // Parses "Sensor at x=2, y=18: closest beacon is at x=-2, y=15"
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let pattern = "Sensor at x=*, y=*: closest beacon is at x=*, y=*";
+        let captures = parse_wildcard(pattern, '*', line)
+            .ok_or_else(|| format!("Expected '{}', but got '{}'", pattern, line))?;
+        let coords = captures.iter()
+            .map(|capture| capture.parse::<i64>().map_err(|error| format!("{}: '{}'", error, capture)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Sensor{
+            position: Point{ x: coords[0], y: coords[1] },
+            beacon: Point{ x: coords[2], y: coords[3] },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SensorMap {
+    sensors: Vec<Sensor>,
+}
+
+impl SensorMap {
+    pub fn new(sensors: Vec<Sensor>) -> SensorMap {
+        SensorMap{ sensors }
+    }
+
+    pub fn sensors(&self) -> &[Sensor] {
+        &self.sensors
+    }
+
+    // Largest absolute coordinate of any sensor or beacon
+    pub fn extent(&self) -> i64 {
+        self.sensors.iter()
+            .flat_map(|sensor| [sensor.position, sensor.beacon])
+            .map(|p| p.x.abs().max(p.y.abs()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Whether some sensor rules out any beacon but its own at `p`
+    pub fn is_covered(&self, p: &Point) -> bool {
+        self.sensors.iter().any(|sensor| sensor.coverage().contains(p))
+    }
+
+    pub fn is_beacon(&self, p: &Point) -> bool {
+        self.sensors.iter().any(|sensor| sensor.beacon == *p)
+    }
+
+    // Covered columns of row `y`
+    pub fn coverage_on_row(&self, y: i64) -> IntervalSet<i64> {
+        self.sensors.iter()
+            .filter_map(|sensor| sensor.coverage().row_span(y))
+            .collect()
+    }
+
+    // Distinct known beacons on row `y`
+    pub fn beacons_on_row(&self, y: i64) -> BTreeSet<Point> {
+        self.sensors.iter()
+            .map(|sensor| sensor.beacon)
+            .filter(|beacon| beacon.y == y)
+            .collect()
+    }
+
+    // Number of cells on row `y` where there is certainly no beacon
+    pub fn cleared_on_row(&self, y: i64) -> i64 {
+        self.coverage_on_row(y).total_length() - self.beacons_on_row(y).len() as i64
+    }
+
+    // Uncovered columns of row `y` within `columns`
+    pub fn gaps_on_row(&self, y: i64, columns: Range<i64>) -> Vec<Range<i64>> {
+        self.coverage_on_row(y).gaps(columns)
+    }
+
+    // Uncovered cells with both coordinates in `0..=max_extend`, found by
+    // scanning the rows. Fails if there is more than one.
+    pub fn find_uncovered_scan(&self, max_extend: i64) -> Result<Option<Point>, String> {
+        let mut found = None;
+        for y in 0..max_extend + 1 {
+            for gap in self.gaps_on_row(y, 0..max_extend + 1) {
+                if gap.end - gap.start > 1 || found.is_some() {
+                    return Err(format!("Multiple uncovered cells, another one on row {}", y));
+                }
+                found = Some(Point{ x: gap.start, y });
+            }
+        }
+        Ok(found)
+    }
+
+    // Same as find_uncovered_scan, but splits the search area into quadrants
+    // until single cells remain, dropping every part that one sensor covers
+    // entirely. As the diamonds are convex, a sensor covers a rectangle when
+    // it covers its four corners, so only the parts around uncovered cells
+    // and where several sensors meet get split further.
+    pub fn find_uncovered_quadrants(&self, max_extend: i64) -> Result<Option<Point>, String> {
+        let balls: Vec<ManhattanBall> = self.sensors.iter().map(|sensor| sensor.coverage()).collect();
+        let halves = |low: i64, high: i64| {
+            let mid = low + (high - low) / 2;
+            if low == high { vec![(low, high)] } else { vec![(low, mid), (mid + 1, high)] }
+        };
+
+        let mut found = None;
+        let mut pending = Vec::new();
+        if max_extend >= 0 {
+            pending.push((Point{ x: 0, y: 0 }, Point{ x: max_extend, y: max_extend }));
+        }
+        while let Some((min, max)) = pending.pop() {
+            let corners = [min, Point{ x: max.x, y: min.y }, Point{ x: min.x, y: max.y }, max];
+            if balls.iter().any(|ball| corners.iter().all(|corner| ball.contains(corner))) {
+                continue;
+            }
+            if min == max {
+                if let Some(other) = found {
+                    return Err(format!("Multiple uncovered cells: {} and {}", other, min));
+                }
+                found = Some(min);
+                continue;
+            }
+            for (min_x, max_x) in halves(min.x, max.x) {
+                for (min_y, max_y) in halves(min.y, max.y) {
+                    pending.push((Point{ x: min_x, y: min_y }, Point{ x: max_x, y: max_y }));
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+impl FromIterator<Sensor> for SensorMap {
+    fn from_iter<I: IntoIterator<Item = Sensor>>(sensors: I) -> Self {
+        SensorMap::new(sensors.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const EXAMPLE: &str = "\
+Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3";
+
+    fn example() -> SensorMap {
+        EXAMPLE.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    //  012345678901
+    // 0......#.....
+    // 1.....###....
+    // 2....#####...
+    // 3...###S###..
+    // 4....B####...
+    // 5.....###....
+    // 6......#.....
+    //
+    // We use half-open interval, so [3, 4) contains only the element 3
+
+    #[test]
+    fn test_coverage_on_row() {
+        let sensor = Sensor{ position: Point{ x: 6, y: 3 }, beacon: Point{ x: 4, y: 4 } };
+        let map = SensorMap::new(vec![sensor]);
+        assert!(map.coverage_on_row(-1).is_empty());
+        assert_eq!(map.coverage_on_row(0), IntervalSet::from(6..7));
+        assert_eq!(map.coverage_on_row(5), IntervalSet::from(5..8));
+        assert_eq!(map.coverage_on_row(6), IntervalSet::from(6..7));
+        assert!(map.coverage_on_row(7).is_empty());
+        assert_eq!(map.cleared_on_row(4), 4);
+
+        let map = SensorMap::new(vec![sensor, Sensor{ position: Point{ x: 10, y: 3 }, beacon: Point{ x: 10, y: 4 } }]);
+        assert_eq!(map.coverage_on_row(3), IntervalSet::from(3..12));
+        assert_eq!(map.gaps_on_row(4, 0..12), vec![0..4, 9..10, 11..12]);
+    }
+
+    #[test]
+    fn test_example() {
+        let map = example();
+        assert_eq!(map.sensors()[1], Sensor{ position: Point{ x: 9, y: 16 }, beacon: Point{ x: 10, y: 16 } });
+        assert_eq!(map.extent(), 25);
+        assert_eq!(map.cleared_on_row(10), 26);
+        assert!(map.is_beacon(&Point{ x: 2, y: 10 }) && map.is_covered(&Point{ x: 2, y: 10 }));
+        assert!(!map.is_covered(&Point{ x: 14, y: 11 }));
+        assert_eq!(map.find_uncovered_scan(20), Ok(Some(Point{ x: 14, y: 11 })));
+        assert_eq!(map.find_uncovered_quadrants(20), Ok(Some(Point{ x: 14, y: 11 })));
+        assert!(map.find_uncovered_scan(30).is_err());
+        assert!(map.find_uncovered_quadrants(30).is_err());
+        assert!("Sensor at x=1, y=2".parse::<Sensor>().is_err());
+    }
+
+    #[test]
+    fn test_uncovered_corner() {
+        let map = SensorMap::new(vec![Sensor{ position: Point{ x: 0, y: 0 }, beacon: Point{ x: 0, y: 9 } }]);
+        assert_eq!(map.find_uncovered_scan(5), Ok(Some(Point{ x: 5, y: 5 })));
+        assert_eq!(map.find_uncovered_quadrants(5), Ok(Some(Point{ x: 5, y: 5 })));
+    }
+
+    fn sensor_with_radius(x: i64, y: i64, radius: i64) -> Sensor {
+        Sensor{ position: Point{ x, y }, beacon: Point{ x: x + radius, y } }
+    }
+
+    #[test]
+    fn test_uncovered_between_parallel_edges() {
+        // The gap at (10, 10) lies between two edges of the same direction,
+        // no edge of the other direction passes next to it
+        let map = SensorMap::new(vec![
+            sensor_with_radius(15, 15, 9), sensor_with_radius(5, 5, 9),
+            sensor_with_radius(15, 5, 8), sensor_with_radius(5, 15, 8),
+            sensor_with_radius(14, 5, 3), sensor_with_radius(-5, -1, 9),
+            sensor_with_radius(16, 2, 10), sensor_with_radius(-1, 24, 4),
+            sensor_with_radius(0, 19, 14), sensor_with_radius(25, 18, 20),
+        ]);
+        assert_eq!(map.find_uncovered_scan(20), Ok(Some(Point{ x: 10, y: 10 })));
+        assert_eq!(map.find_uncovered_quadrants(20), Ok(Some(Point{ x: 10, y: 10 })));
+    }
+
+    proptest! {
+        // Sensors around a cell they never cover, mostly reaching right up to
+        // it, so that often it is the only uncovered one
+        #[test]
+        fn quadrants_agree_with_scan(
+            gap in (0..16i64, 0..16i64),
+            sensors in prop::collection::vec((-4..20i64, -4..20i64, 0..3i64), 1..24),
+        ) {
+            let gap = Point{ x: gap.0, y: gap.1 };
+            let map: SensorMap = sensors.iter()
+                .filter(|(x, y, _)| Point{ x: *x, y: *y } != gap)
+                .map(|(x, y, shrink)| {
+                    let distance = (gap - Point{ x: *x, y: *y }).l1_norm();
+                    sensor_with_radius(*x, *y, (distance - 1 - shrink).max(0))
+                })
+                .collect();
+            let scan = map.find_uncovered_scan(15);
+            prop_assert_eq!(map.find_uncovered_quadrants(15).ok(), scan.clone().ok());
+            if let Ok(found) = scan {
+                prop_assert_eq!(found, Some(gap));
+            }
+        }
+    }
+}
@@ -1,5 +1,33 @@
 use std::io;
 use std::io::Read;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::fmt::Debug;
+
+use crate::parser::{parse_records, Parser, Records};
+
+// Value of a `--name value` or `--name=value` command line option
+#[allow(dead_code)]
+pub fn option_value(name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(&flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_owned());
+        }
+    }
+    None
+}
+
+// Whether a `--name` switch was given on the command line
+#[allow(dead_code)]
+pub fn has_flag(name: &str) -> bool {
+    let flag = format!("--{}", name);
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
 
 pub struct EasyLines {}
 
@@ -33,6 +61,11 @@ pub fn input_all() -> String {
     buf
 }
 
+#[allow(dead_code)]
+pub fn input_records(parser: Rc<dyn Parser>) -> Records<io::StdinLock<'static>> {
+    parse_records(io::stdin().lock(), parser)
+}
+
 pub struct EasyLinesIndices {
     line: usize,
 }
@@ -59,6 +92,94 @@ pub fn input_lines_indices() -> EasyLinesIndices {
 }
 
 
+// Parses each non-empty line, tagging failures with the 0-based line number
+pub fn parse_numbered_lines<I, T, E, ParseFn>(lines: I, parser: ParseFn) -> impl Iterator<Item = Result<T, (usize, E)>>
+where
+    I: Iterator<Item = (usize, String)>,
+    ParseFn: Fn(&str) -> Result<T, E>
+{
+    lines
+        .filter(|(_, line)| !line.is_empty())
+        .map(move |(line_no, line)| parser(&line).map_err(|error| (line_no, error)))
+}
+
+#[allow(dead_code)]
+pub fn parse_lines_with<T, E, ParseFn>(parser: ParseFn) -> impl Iterator<Item = Result<T, (usize, E)>>
+where
+    ParseFn: Fn(&str) -> Result<T, E>
+{
+    parse_numbered_lines(input_lines_indices(), parser)
+}
+
+#[allow(dead_code)]
+pub fn parse_lines<T: FromStr>() -> impl Iterator<Item = Result<T, (usize, T::Err)>> {
+    parse_lines_with(|line| line.parse::<T>())
+}
+
+// Unwraps a parsed line, panicking with the offending (1-based) line number
+pub fn expect_line<T, E: Debug>(result: Result<T, (usize, E)>) -> T {
+    match result {
+        Ok(value) => value,
+        Err((line_no, error)) => panic!("Invalid input on line {}: {:?}", line_no + 1, error),
+    }
+}
+
+// Block of consecutive non-blank lines, each with its 0-based line number in the
+// input. Lines keep their leading whitespace, only line terminators are stripped.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub lines: Vec<(usize, String)>,
+}
+
+impl Section {
+    pub fn first_line(&self) -> usize {
+        self.lines.first().map_or(0, |(line_no, _)| *line_no)
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|(_, line)| line.as_str())
+    }
+}
+
+pub struct Sections<I: Iterator<Item = (usize, String)>> {
+    lines: I,
+}
+
+impl <I: Iterator<Item = (usize, String)>> Iterator for Sections<I> {
+    type Item = Section;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut lines = Vec::new();
+        for (line_no, line) in self.lines.by_ref() {
+            if !line.trim().is_empty() {
+                lines.push((line_no, line));
+            } else if !lines.is_empty() {
+                break;
+            }
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(Section{ lines })
+        }
+    }
+}
+
+// Splits numbered lines into blank-line-separated sections. Runs of several
+// blank lines count as a single separator. Sections can differ in format, e.g.
+// a header block followed by commands is read with two calls to `next()`.
+pub fn sections<I: Iterator<Item = (usize, String)>>(lines: I) -> Sections<I> {
+    Sections{ lines }
+}
+
+#[allow(dead_code)]
+pub fn input_sections() -> Sections<impl Iterator<Item = (usize, String)>> {
+    let lines = io::stdin().lines()
+        .map(|line| line.unwrap_or_else(|error| panic!("Error: {}", error)))
+        .enumerate();
+    sections(lines)
+}
+
 pub fn run_on_input<LineFn, FinFn, State, Result>(init: State, on_line: LineFn, on_done: FinFn) -> Result
 where
     LineFn: Fn(&str, State) -> State,
@@ -78,4 +199,38 @@ where
             Err(error) => panic!("Error: {}", error)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+        text.lines().map(|line| line.to_owned()).enumerate()
+    }
+
+    #[test]
+    fn test_sections() {
+        let text = "\n    [D]\n[N] [C]\n\n\nmove 1 from 2 to 1\n  \nlast\n";
+        let found: Vec<Section> = sections(numbered(text)).collect();
+
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].first_line(), 1);
+        assert_eq!(found[0].lines().collect::<Vec<&str>>(), vec!["    [D]", "[N] [C]"]);
+        assert_eq!(found[1].lines, vec![(5, "move 1 from 2 to 1".to_owned())]);
+        assert_eq!(found[2].lines, vec![(7, "last".to_owned())]);
+
+        assert_eq!(sections(numbered("\n \n")).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_numbered_lines() {
+        let parsed: Vec<Result<i32, (usize, std::num::ParseIntError)>> =
+            parse_numbered_lines(numbered("12\n\n-3\nx4\n"), |line| line.parse()).collect();
+
+        assert_eq!(parsed.len(), 3);
+        assert_matches!(parsed[0], Ok(12));
+        assert_matches!(parsed[1], Ok(-3));
+        assert_matches!(parsed[2], Err((3, _)));
+    }
+}